@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
 };
@@ -7,6 +8,9 @@ use ecow::EcoVec;
 
 use crate::{cowslice::CowSlice, Form, Ori, Ufel, UfelResult};
 
+/// The maximum number of elements an array created from a computed size may have
+pub const MAX_ARRAY_ELEMS: usize = 1 << 30;
+
 #[derive(Clone)]
 #[allow(clippy::manual_non_exhaustive)]
 pub struct Array<T = f64> {
@@ -51,30 +55,50 @@ impl<T: Clone> Array<T> {
             self.data.len()
         );
     }
-    pub fn from_row_arrays(rows: impl IntoIterator<Item = Self>, rt: &Ufel) -> UfelResult<Self> {
+    pub fn from_row_arrays(rows: impl IntoIterator<Item = Self>, rt: &Ufel) -> UfelResult<Self>
+    where
+        T: Element,
+    {
+        Self::from_row_arrays_ori(rows, rt.ori(), rt)
+    }
+    pub(crate) fn from_row_arrays_ori(
+        rows: impl IntoIterator<Item = Self>,
+        ori: Ori,
+        rt: &Ufel,
+    ) -> UfelResult<Self>
+    where
+        T: Element,
+    {
+        let mut rows: Vec<Self> = rows.into_iter().collect();
+        if let Some(fill) = T::fill(rt) {
+            if let Some(first) = rows.first() {
+                let target = (rows.iter().skip(1)).fold(first.form.clone(), |target, row| {
+                    target.fill_target(&row.form)
+                });
+                for row in &mut rows {
+                    row.fill_to(&row.form.fill_target(&target), fill.clone());
+                }
+            }
+        }
         let mut iter = rows.into_iter();
         let Some(mut arr) = iter.next() else {
             return Ok(Array::default());
         };
-        if arr.form.is_normal() {
-            let mut new_len = 1;
-            for row in iter {
-                if row.form != arr.form {
-                    return Err(rt.error(format!(
-                        "Cannot create array with different row forms {:?} and {:?}",
-                        arr.form, row.form
-                    )));
-                }
-                arr.data.extend_from_cowslice(row.data);
-                new_len += 1;
+        let mut new_len = 1;
+        for row in iter {
+            if row.form != arr.form {
+                return Err(rt.error(format!(
+                    "Cannot create array with different row forms {:?} and {:?}",
+                    arr.form, row.form
+                )));
             }
-            arr.form.fix(rt.ori());
-            arr.form[0][0] = new_len;
-            arr.validate_form();
-            Ok(arr)
-        } else {
-            todo!("non-normal array creation")
+            arr.data.extend_from_cowslice(row.data);
+            new_len += 1;
         }
+        arr.form.fix(ori);
+        arr.form[0][0] = new_len;
+        arr.validate_form();
+        Ok(arr)
     }
 }
 
@@ -83,15 +107,15 @@ impl<T: Clone + 'static> Array<T> {
         let row_count = self.form.row_count(ori);
         let row_form = self.form.row(ori);
         let row_len = row_form.elems();
-        match ori {
-            Ori::Hori => Box::new((0..row_count).map(move |i| {
+        match self.form.row_order(ori) {
+            None => Box::new((0..row_count).map(move |i| {
                 let data = self.data.slice(i * row_len..(i + 1) * row_len);
                 Array::new(row_form.clone(), data)
             })),
-            Ori::Vert => Box::new((0..row_count).map(move |i| {
+            Some(order) => Box::new((0..row_count).map(move |i| {
                 let mut data = EcoVec::with_capacity(row_len);
-                for j in 0..row_len {
-                    data.push(self.data[i * row_len + j].clone());
+                for &j in &order[i * row_len..(i + 1) * row_len] {
+                    data.push(self.data[j].clone());
                 }
                 Array::new(row_form.clone(), data.into())
             })),
@@ -99,7 +123,51 @@ impl<T: Clone + 'static> Array<T> {
     }
 }
 
-impl<T: Element> Array<T> {}
+impl<T: Element> Array<T> {
+    /// Get the array's data with the rows of the given orientation contiguous
+    pub(crate) fn row_data(&self, ori: Ori) -> CowSlice<T> {
+        match self.form.row_order(ori) {
+            None => self.data.clone(),
+            Some(order) => order.iter().map(|&i| self.data[i].clone()).collect(),
+        }
+    }
+    /// Set the array's data from data with the rows of the given orientation contiguous
+    pub(crate) fn set_row_data(&mut self, ori: Ori, data: CowSlice<T>) {
+        match self.form.row_order(ori) {
+            None => self.data = data,
+            Some(order) => {
+                let slice = self.data.as_mut_slice();
+                for (&i, elem) in order.iter().zip(data) {
+                    slice[i] = elem;
+                }
+            }
+        }
+    }
+    /// Pad the array with a fill value to a form with the same form matrix
+    /// size whose axes are all at least as long
+    pub(crate) fn fill_to(&mut self, target: &Form, fill: T) {
+        if self.form == *target {
+            return;
+        }
+        let dims = self.form.dims();
+        let mut data = EcoVec::with_capacity(target.elems());
+        let mut index = vec![0; dims.len()];
+        for flat in 0..target.elems() {
+            let mut rem = flat;
+            for (k, &dim) in target.dims().iter().enumerate().rev() {
+                index[k] = rem % dim;
+                rem /= dim;
+            }
+            if index.iter().zip(dims).all(|(&i, &d)| i < d) {
+                let src = (index.iter().zip(dims)).fold(0, |acc, (&i, &d)| acc * d + i);
+                data.push(self.data[src].clone());
+            } else {
+                data.push(fill.clone());
+            }
+        }
+        *self = Array::new(target.clone(), data.into());
+    }
+}
 
 impl<T: Element> PartialEq for Array<T> {
     fn eq(&self, other: &Self) -> bool {
@@ -122,6 +190,9 @@ impl<T: Element> Hash for Array<T> {
 pub trait Element: fmt::Debug + fmt::Display + Clone + Send + Sync + 'static {
     fn array_eq(&self, other: &Self) -> bool;
     fn array_hash<H: Hasher>(&self, state: &mut H);
+    fn array_cmp(&self, other: &Self) -> Ordering;
+    /// Get the current fill value for this element type
+    fn fill(rt: &Ufel) -> Option<Self>;
 }
 
 impl Element for f64 {
@@ -135,6 +206,13 @@ impl Element for f64 {
             self.to_bits().hash(state);
         }
     }
+    fn array_cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other)
+            .unwrap_or_else(|| self.is_nan().cmp(&other.is_nan()))
+    }
+    fn fill(rt: &Ufel) -> Option<Self> {
+        rt.fill()
+    }
 }
 
 impl<T: Clone> From<T> for Array<T> {
@@ -186,6 +264,12 @@ impl From<&[usize]> for Array {
     }
 }
 
+impl From<&str> for Array {
+    fn from(s: &str) -> Self {
+        s.chars().map(|c| c as u32 as f64).collect()
+    }
+}
+
 impl From<Form> for Array {
     fn from(form: Form) -> Self {
         let mut arr: Array = form.dims().into();
@@ -1,17 +1,37 @@
 use std::fmt;
 
-use crate::{DyMod, Dyadic, Mod, Monadic, Sp, Span};
+use ecow::EcoString;
+
+use crate::{DyMod, Dyadic, Mod, Monadic, Signature, Sp, Span};
 
 #[derive(Debug, Clone)]
 pub enum Item {
     Words(Vec<Sp<Word>>),
+    Binding(Binding),
+    Import(Import),
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub prefix: Sp<EcoString>,
+    pub path: Sp<EcoString>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub name: Sp<EcoString>,
+    pub sig: Option<Sp<Signature>>,
+    pub words: Vec<Sp<Word>>,
 }
 
 #[derive(Clone)]
 pub enum Word {
     Number(f64),
+    Ident(EcoString),
+    Str(EcoString),
     Func(Func),
     Array(Array),
+    Formed(Array),
     Mon(Monadic),
     Dy(Dyadic),
     Mod(Modified),
@@ -21,8 +41,11 @@ impl fmt::Debug for Word {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Word::Number(n) => n.fmt(f),
+            Word::Ident(name) => write!(f, "@{name}"),
+            Word::Str(s) => write!(f, "@{s:?}"),
             Word::Func(fu) => fu.fmt(f),
             Word::Array(a) => a.fmt(f),
+            Word::Formed(a) => a.fmt(f),
             Word::Mon(m) => m.fmt(f),
             Word::Dy(d) => d.fmt(f),
             Word::Mod(m) => m.fmt(f),
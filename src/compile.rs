@@ -1,15 +1,29 @@
-use std::mem::take;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    mem::{replace, take},
+    path::PathBuf,
+};
 
-use ecow::EcoString;
+use ecow::{EcoString, EcoVec};
 
 use crate::{
-    ast::*, parse, InputSrc, Inputs, Node, Sp, Span, UfelError, UfelErrorKind, UfelResult,
+    ast::*, parse, DyMod, InputSrc, Inputs, Mod, Node, SigNode, Signature, Sp, Span, UfelError,
+    UfelErrorKind, UfelResult,
 };
 
 #[derive(Clone, Default)]
 pub struct Compiler {
     pub asm: Assembly,
     pub errors: Vec<UfelError>,
+    /// The indices into [`Assembly::functions`] of bound names
+    names: HashMap<EcoString, usize>,
+    /// The functions currently being compiled that have no signature annotation
+    unannotated: Vec<usize>,
+    /// The canonical paths of the files currently being loaded
+    loading: Vec<PathBuf>,
+    /// The bindings of already imported files
+    modules: HashMap<PathBuf, HashMap<EcoString, usize>>,
 }
 
 #[derive(Clone, Default)]
@@ -17,6 +31,8 @@ pub struct Assembly {
     pub root: Node,
     pub inputs: Inputs,
     pub spans: Vec<Span>,
+    /// The bodies of bound functions
+    pub functions: EcoVec<SigNode>,
 }
 
 impl Compiler {
@@ -27,6 +43,20 @@ impl Compiler {
         self.load_impl(InputSrc::Str, text.into())
     }
     fn load_impl(&mut self, src: InputSrc, text: EcoString) -> UfelResult {
+        let path = match &src {
+            InputSrc::File(path) => path.canonicalize().ok(),
+            InputSrc::Str => None,
+        };
+        if let Some(path) = &path {
+            self.loading.push(path.clone());
+        }
+        let res = self.load_items(src, text);
+        if path.is_some() {
+            self.loading.pop();
+        }
+        res
+    }
+    fn load_items(&mut self, src: InputSrc, text: EcoString) -> UfelResult {
         let (items, errors) = parse(src, text, &mut self.asm.inputs);
         if let Some(error) =
             UfelError::from_iter(errors.into_iter().map(|e| self.asm.inputs.human_sp(e)))
@@ -54,7 +84,90 @@ impl Compiler {
                 let node = self.line(words)?;
                 self.asm.root.push(node);
             }
+            Item::Binding(binding) => self.binding(binding)?,
+            Item::Import(import) => self.import(import)?,
+        }
+        Ok(())
+    }
+    fn import(&mut self, import: Import) -> UfelResult {
+        let span = import.path.span;
+        // Paths are relative to the importing file
+        let path = match &self.asm.inputs.inputs[span.src].src {
+            InputSrc::File(importer) => importer.parent().map_or_else(
+                || import.path.value.as_str().into(),
+                |dir| dir.join(import.path.value.as_str()),
+            ),
+            InputSrc::Str => PathBuf::from(import.path.value.as_str()),
+        };
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| self.error(span, format!("Failed to import {}: {e}", path.display())))?;
+        let names = if let Some(names) = self.modules.get(&canonical) {
+            names.clone()
+        } else {
+            if let Some(i) = self.loading.iter().position(|p| *p == canonical) {
+                let cycle: Vec<String> = (self.loading[i..].iter())
+                    .chain([&canonical])
+                    .map(|p| p.display().to_string())
+                    .collect();
+                return Err(self.error(span, format!("Import cycle: {}", cycle.join(" -> "))));
+            }
+            let text = fs::read_to_string(&path).map_err(|e| {
+                self.error(span, format!("Failed to import {}: {e}", path.display()))
+            })?;
+            // Only the bindings of an imported file are used
+            let outer_names = take(&mut self.names);
+            let outer_root = take(&mut self.asm.root);
+            let outer_errors = take(&mut self.errors);
+            let res = self.load(InputSrc::File(path), text);
+            self.errors = outer_errors;
+            self.asm.root = outer_root;
+            let names = replace(&mut self.names, outer_names);
+            res?;
+            self.modules.insert(canonical, names.clone());
+            names
+        };
+        for (name, index) in names {
+            (self.names).insert(format!("{}.{name}", import.prefix.value).into(), index);
+        }
+        Ok(())
+    }
+    fn binding(&mut self, binding: Binding) -> UfelResult {
+        let index = self.asm.functions.len();
+        let annotated = binding.sig.as_ref().map(|sig| sig.value);
+        (self.asm.functions).push(SigNode::new(Node::empty(), annotated.unwrap_or_default()));
+        // The name is bound before the body is compiled to allow recursion
+        let prev = self.names.insert(binding.name.value.clone(), index);
+        if annotated.is_none() {
+            self.unannotated.push(index);
+        }
+        let res = self.line(binding.words);
+        if annotated.is_none() {
+            self.unannotated.pop();
+        }
+        let node = match res {
+            Ok(node) => node,
+            Err(e) => {
+                match prev {
+                    Some(prev) => self.names.insert(binding.name.value, prev),
+                    None => self.names.remove(&binding.name.value),
+                };
+                return Err(e);
+            }
+        };
+        let sig = node.sig();
+        if let Some(annotated) = binding.sig {
+            if annotated.value != sig {
+                self.add_error(
+                    annotated.span,
+                    format!(
+                        "@{}'s signature {:?} does not match its annotation {:?}",
+                        binding.name.value, sig, annotated.value
+                    ),
+                );
+            }
         }
+        self.asm.functions.make_mut()[index] = SigNode::new(node, sig);
         Ok(())
     }
     fn line(&mut self, words: Vec<Sp<Word>>) -> UfelResult<Node> {
@@ -67,6 +180,21 @@ impl Compiler {
     fn word(&mut self, word: Sp<Word>) -> UfelResult<Node> {
         Ok(match word.value {
             Word::Number(n) => Node::new_push(n),
+            Word::Ident(name) => {
+                let Some(&index) = self.names.get(&name) else {
+                    return Err(self.error(word.span, format!("Unknown name @{name}")));
+                };
+                if self.unannotated.contains(&index) {
+                    return Err(self.error(
+                        word.span,
+                        format!("Recursive function @{name} must have a signature annotation"),
+                    ));
+                }
+                let sig = self.asm.functions[index].sig;
+                let span = self.add_span(word.span);
+                Node::Call(index, sig, span)
+            }
+            Word::Str(s) => Node::new_push(s.as_str()),
             Word::Func(func) => {
                 let mut node = Node::empty();
                 for line in func.lines {
@@ -83,6 +211,24 @@ impl Compiler {
                 let sig = inner.sig();
                 Node::Array(sig.outputs, inner.into(), span)
             }
+            Word::Formed(array) => {
+                let mut inner = Node::empty();
+                for line in array.lines {
+                    inner.push(self.line(line)?);
+                }
+                let sig = inner.sig();
+                if sig.outputs != 2 {
+                    self.add_error(
+                        word.span,
+                        format!(
+                            "Form literal must contain data and a form, \
+                            but its signature is {sig:?}"
+                        ),
+                    );
+                }
+                let span = self.add_span(word.span);
+                Node::Formed(inner.into(), span)
+            }
             Word::Mon(monadic) => {
                 let span = self.add_span(word.span);
                 Node::Mon(monadic, span)
@@ -95,6 +241,9 @@ impl Compiler {
         })
     }
     fn modified(&mut self, modified: Modified, span: Span) -> UfelResult<Node> {
+        if let Modifier::Mon(Mod::Switch) = modified.modifier.value {
+            return self.switch(modified);
+        }
         if modified.args.len() > modified.modifier.value.arg_count() {
             self.add_error(
                 span,
@@ -113,17 +262,104 @@ impl Compiler {
         Ok(match modified.modifier.value {
             Modifier::Mon(m) => {
                 let f = args.next().transpose()?.unwrap_or_default();
+                if matches!(m, Mod::Repeat | Mod::FixedPoint) {
+                    self.require_balanced(&m, &f, span);
+                }
+                let f = if m == Mod::Un { self.un(f, span) } else { f };
                 let span = self.add_span(modified.modifier.span);
                 Node::Mod(m, f.into(), span)
             }
             Modifier::Dy(d) => {
                 let f = args.next().transpose()?.unwrap_or_default();
                 let g = args.next().transpose()?.unwrap_or_default();
+                if d == DyMod::Do {
+                    self.require_balanced(&d, &f, span);
+                }
+                if d == DyMod::Try
+                    && (g.sig.outputs != f.sig.outputs || g.sig.args > f.sig.args + 1)
+                {
+                    self.add_error(
+                        span,
+                        format!(
+                            "{d:?}'s handler must have {} outputs and at most {} arguments, \
+                            but its signature is {:?}",
+                            f.sig.outputs,
+                            f.sig.args + 1,
+                            g.sig
+                        ),
+                    );
+                }
+                let g = if d == DyMod::Under {
+                    let inv = self.un(f.clone(), span);
+                    Node::from_iter([g.node, inv.node]).sig_node()
+                } else {
+                    g
+                };
                 let span = self.add_span(modified.modifier.span);
                 Node::DyMod(d, f.into(), g.into(), span)
             }
         })
     }
+    fn switch(&mut self, modified: Modified) -> UfelResult<Node> {
+        let mut branches = EcoVec::with_capacity(modified.args.len());
+        let mut spans = Vec::with_capacity(modified.args.len());
+        for word in modified.args {
+            spans.push(word.span);
+            branches.push(self.word(word)?.sig_node());
+        }
+        if let Some(first) = branches.first() {
+            let first_sig = first.sig;
+            let net = |sig: Signature| sig.outputs as i32 - sig.args as i32;
+            for (branch, &span) in branches.iter().zip(&spans).skip(1) {
+                if net(branch.sig) != net(first_sig) {
+                    self.add_error(
+                        span,
+                        format!(
+                            "{:?} branch signature {:?} is not compatible \
+                            with the first branch's signature {:?}",
+                            Mod::Switch,
+                            branch.sig,
+                            first_sig
+                        ),
+                    );
+                }
+            }
+        }
+        let span = self.add_span(modified.modifier.span);
+        Ok(Node::Switch(branches, span))
+    }
+    /// Invert a function, reporting an error at the span of
+    /// the first node that has no inverse
+    fn un(&mut self, f: SigNode, span: Span) -> SigNode {
+        match f.node.un() {
+            Ok(node) => node.sig_node(),
+            Err(node) => {
+                let span = (node.span()).map_or(span, |i| self.asm.spans[i]);
+                let message = match node {
+                    Node::Mon(prim, _) => format!("{prim:?} has no inverse"),
+                    Node::Dy(prim, _) => format!("{prim:?} has no inverse"),
+                    Node::Mod(prim, ..) => format!("{prim:?} has no inverse"),
+                    Node::DyMod(prim, ..) => format!("{prim:?} has no inverse"),
+                    node => format!("{node:?} has no inverse"),
+                };
+                self.add_error(span, message);
+                f
+            }
+        }
+    }
+    /// Require that a looped function has as many outputs as arguments
+    fn require_balanced(&mut self, prim: &impl fmt::Debug, f: &SigNode, span: Span) {
+        if f.sig.args != f.sig.outputs {
+            self.add_error(
+                span,
+                format!(
+                    "{prim:?}'s function must have as many outputs as arguments, \
+                    but its signature is {:?}",
+                    f.sig
+                ),
+            );
+        }
+    }
     fn add_span(&mut self, span: Span) -> usize {
         self.asm.spans.push(span);
         self.asm.spans.len() - 1
@@ -135,3 +371,35 @@ impl Compiler {
         UfelErrorKind::Compile(self.asm.inputs.error(span, message.into())).into()
     }
 }
+
+#[cfg(test)]
+#[test]
+fn imports() {
+    use crate::Ufel;
+    let dir = std::env::temp_dir().join(format!("ufel-imports-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    let write = |name: &str, text: &str| fs::write(dir.join(name), text).unwrap();
+    write("math.fel", "@square = (:*)");
+    write(
+        "sub/inner.fel",
+        "@m = @import @\"../math.fel\"\n@quad = (@m.square @m.square)",
+    );
+    write("main.fel", "@s = @import @\"sub/inner.fel\"\n3 @s.quad");
+    write("a.fel", "@b = @import @\"b.fel\"");
+    write("b.fel", "@a = @import @\"a.fel\"");
+    write("missing.fel", "@x = @import @\"nope.fel\"");
+    let run = |name: &str| {
+        let path = dir.join(name);
+        let text = fs::read_to_string(&path).unwrap();
+        let mut rt = Ufel::new();
+        rt.run(InputSrc::File(path), text).map(|_| rt.take_stack())
+    };
+    let stack = run("main.fel");
+    let a = run("a.fel").unwrap_err().to_string();
+    let missing = run("missing.fel").unwrap_err().to_string();
+    fs::remove_dir_all(&dir).unwrap();
+    // Imported paths are relative to the importing file
+    assert_eq!(stack.unwrap(), crate::test_run("81"));
+    assert!(a.contains("Import cycle"), "{a}");
+    assert!(missing.contains("Failed to import"), "{missing}");
+}
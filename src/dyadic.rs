@@ -1,4 +1,11 @@
-use crate::{Array, Element, Form, FormDims, Ori, Ufel, UfelResult};
+use std::collections::HashMap;
+
+use ecow::EcoVec;
+
+use crate::{
+    monadic::flat_to_dims, Array, Dyadic, Element, Form, FormDims, Ori, Ufel, UfelResult,
+    MAX_ARRAY_ELEMS,
+};
 
 impl<T: Element> Array<T> {
     pub fn chunk(mut self, size: Array, rt: &Ufel) -> UfelResult<Self> {
@@ -39,7 +46,7 @@ impl<T: Element> Array<T> {
                     let dim = self.form.dims()[i];
                     if let Some(&sz) = size.get(i) {
                         let abs_sz = (sz as isize).unsigned_abs();
-                        if abs_sz == 0 || dim % abs_sz != 0 {
+                        if abs_sz == 0 || !dim.is_multiple_of(abs_sz) {
                             return Err(rt.error(format!(
                                 "Chunk size {sz} does not evenly divide axis {i} size {dim}"
                             )));
@@ -61,7 +68,14 @@ impl<T: Element> Array<T> {
                         new_dims.push(self.form.dims()[i * self.form.hori_rank() + j]);
                     }
                 }
-                self.form = Form::new(self.form.vert_rank() + 1, self.form.hori_rank(), new_dims);
+                // The count axes keep the labels of the chunked axes
+                let hori = self.form.hori_rank();
+                let sources = (0..hori)
+                    .flat_map(|i| [Some(i), None])
+                    .chain((hori..self.form.dims_rank()).map(Some));
+                let mut new_form = Form::new(self.form.vert_rank() + 1, hori, new_dims);
+                new_form.relabel(&self.form, sources);
+                self.form = new_form;
                 self.validate_form();
                 self.move_axes(&dests, rt)
             }
@@ -69,3 +83,804 @@ impl<T: Element> Array<T> {
         }
     }
 }
+
+impl<T: Element> Array<T> {
+    /// Merge the axes split by [`Array::chunk`]
+    pub fn unchunk(self, rt: &Ufel) -> UfelResult<Self> {
+        let (vert, hori) = (self.form.vert_rank(), self.form.hori_rank());
+        let ori = rt.ori();
+        let (chunk_rank, other_rank) = match ori {
+            Ori::Hori => (vert, hori),
+            Ori::Vert => (hori, vert),
+        };
+        if chunk_rank < 2 || other_rank == 0 {
+            return Err(rt.error(format!(
+                "Cannot unchunk an array with {} form {:?}",
+                ori.str(),
+                self.form
+            )));
+        }
+        let dims = self.form.dims();
+        match ori {
+            Ori::Hori => {
+                // Interleave the first two form rows so that they can be merged
+                let dests: Vec<usize> = (0..vert * hori)
+                    .map(|k| match (k / hori, k % hori) {
+                        (0, j) => 2 * j,
+                        (1, j) => 2 * j + 1,
+                        _ => k,
+                    })
+                    .collect();
+                let mut new_dims: FormDims = (0..hori).map(|j| dims[j] * dims[hori + j]).collect();
+                new_dims.extend_from_slice(&dims[2 * hori..]);
+                let mut arr = self.move_axes(&dests, rt)?;
+                let mut new_form = Form::new(vert - 1, hori, new_dims);
+                let sources = (0..hori)
+                    .map(|j| Some(2 * j))
+                    .chain((2 * hori..vert * hori).map(Some));
+                new_form.relabel(&arr.form, sources);
+                arr.form = new_form;
+                Ok(arr)
+            }
+            Ori::Vert => {
+                // The first two form columns are already adjacent in each row
+                let mut new_dims = FormDims::with_capacity(vert * (hori - 1));
+                for row in dims.chunks_exact(hori) {
+                    new_dims.push(row[0] * row[1]);
+                    new_dims.extend_from_slice(&row[2..]);
+                }
+                let mut arr = self;
+                let mut new_form = Form::new(vert, hori - 1, new_dims);
+                let sources = (0..vert * hori).filter(|k| k % hori != 1).map(Some);
+                new_form.relabel(&arr.form, sources);
+                arr.form = new_form;
+                Ok(arr)
+            }
+        }
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn select(self, indices: Array, rt: &Ufel) -> UfelResult<Self> {
+        self.select_ori(indices, rt.ori(), rt)
+    }
+    fn select_ori(self, indices: Array, ori: Ori, rt: &Ufel) -> UfelResult<Self> {
+        if !indices.form.is_normal() {
+            return Err(rt.error(format!(
+                "{:?} indices must be normal, but their form is {:?}",
+                Dyadic::Select,
+                indices.form
+            )));
+        }
+        let row_count = self.form.row_count(ori);
+        let row_form = self.form.row(ori);
+        let row_len = row_form.elems();
+        let order = self.form.row_order(ori);
+        let row = |i: f64| -> UfelResult<Self> {
+            let i = normalize_index(i, row_count, Dyadic::Select, ori, rt)?;
+            Ok(match &order {
+                None => Array::new(
+                    row_form.clone(),
+                    self.data.slice(i * row_len..(i + 1) * row_len),
+                ),
+                Some(order) => {
+                    let mut data = EcoVec::with_capacity(row_len);
+                    for &j in &order[i * row_len..(i + 1) * row_len] {
+                        data.push(self.data[j].clone());
+                    }
+                    Array::new(row_form.clone(), data.into())
+                }
+            })
+        };
+        if indices.form.is_scalar() {
+            return row(indices.data[0]);
+        }
+        if indices.form.is_list() {
+            let mut data = EcoVec::with_capacity(indices.data.len() * row_len);
+            for &i in &indices.data {
+                data.extend(row(i)?.data.iter().cloned());
+            }
+            let mut form = row_form.clone();
+            form.fix(ori);
+            form[0][0] = indices.data.len();
+            return Ok(Array::new(form, data.into()));
+        }
+        let rows = (indices.into_rows(ori))
+            .map(|indices| self.clone().select_ori(indices, ori, rt))
+            .collect::<UfelResult<Vec<_>>>()?;
+        Array::from_row_arrays_ori(rows, ori, rt)
+    }
+    pub fn pick(self, index: Array, rt: &Ufel) -> UfelResult<Self> {
+        if !index.form.is_normal() {
+            return Err(rt.error(format!(
+                "{:?} index must be normal, but its form is {:?}",
+                Dyadic::Pick,
+                index.form
+            )));
+        }
+        if index.form.is_scalar() {
+            return self.select(index, rt);
+        }
+        if index.form.is_list() {
+            let mut arr = self;
+            for &i in &index.data {
+                arr = arr.select(Array::scalar(i), rt)?;
+            }
+            return Ok(arr);
+        }
+        // Index vectors lie along the last horizontal axis, as they do for range
+        let rows = (index.into_rows(Ori::Hori))
+            .map(|index| self.clone().pick(index, rt))
+            .collect::<UfelResult<Vec<_>>>()?;
+        Array::from_row_arrays_ori(rows, Ori::Hori, rt)
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn take(self, counts: Array, rt: &Ufel) -> UfelResult<Self> {
+        let axes = self.form.shape_axes(rt.ori());
+        let counts = axis_counts(&counts, axes.len(), Dyadic::Take, rt)?;
+        let mut ranges = Vec::with_capacity(counts.len());
+        for (k, (&n, &pos)) in counts.iter().zip(&axes).enumerate() {
+            let dim = self.form.dims()[pos];
+            let abs_n = n.unsigned_abs();
+            if abs_n > dim {
+                return Err(rt.error(format!(
+                    "Cannot take {n} from {} axis {k} of length {dim}",
+                    rt.ori().str()
+                )));
+            }
+            let start = if n >= 0 { 0 } else { dim - abs_n };
+            ranges.push((pos, start, abs_n));
+        }
+        Ok(self.slice_axes(&ranges))
+    }
+    pub fn drop(self, counts: Array, rt: &Ufel) -> UfelResult<Self> {
+        let axes = self.form.shape_axes(rt.ori());
+        let counts = axis_counts(&counts, axes.len(), Dyadic::Drop, rt)?;
+        let mut ranges = Vec::with_capacity(counts.len());
+        for (&n, &pos) in counts.iter().zip(&axes) {
+            let dim = self.form.dims()[pos];
+            let abs_n = n.unsigned_abs().min(dim);
+            let start = if n >= 0 { abs_n } else { 0 };
+            ranges.push((pos, start, dim - abs_n));
+        }
+        Ok(self.slice_axes(&ranges))
+    }
+    pub fn rotate(self, counts: Array, rt: &Ufel) -> UfelResult<Self> {
+        let axes = self.form.shape_axes(rt.ori());
+        let counts = axis_counts(&counts, axes.len(), Dyadic::Rotate, rt)?;
+        let mut shifts = vec![0; self.form.dims_rank()];
+        for (&n, &pos) in counts.iter().zip(&axes) {
+            let dim = self.form.dims()[pos];
+            if dim > 0 {
+                shifts[pos] = n.rem_euclid(dim as isize) as usize;
+            }
+        }
+        if shifts.iter().all(|&s| s == 0) {
+            return Ok(self);
+        }
+        let dims = FormDims::from(self.form.dims());
+        Ok(self.remap_axes(dims.clone(), |pos, i| (i + shifts[pos]) % dims[pos]))
+    }
+    /// Slice along some axes, given as `(axis, start, len)`
+    fn slice_axes(mut self, ranges: &[(usize, usize, usize)]) -> Self {
+        let mut new_dims = FormDims::from(self.form.dims());
+        let mut starts = vec![0; new_dims.len()];
+        for &(pos, start, len) in ranges {
+            new_dims[pos] = len;
+            starts[pos] = start;
+        }
+        if new_dims.as_slice() == self.form.dims() {
+            return self;
+        }
+        // Only the first axis changes, so the data is contiguous
+        if (new_dims.iter().zip(self.form.dims()).skip(1)).all(|(a, b)| a == b) {
+            let row_len: usize = new_dims.iter().skip(1).product();
+            let start = starts[0] * row_len;
+            self.data = self.data.slice(start..start + new_dims[0] * row_len);
+            self.form = self.form.with_dims(new_dims);
+            self.validate_form();
+            return self;
+        }
+        self.remap_axes(new_dims, |pos, i| starts[pos] + i)
+    }
+    /// Build an array with new dims, where each index along each axis
+    /// is mapped to an index into the original data
+    fn remap_axes(self, new_dims: FormDims, f: impl Fn(usize, usize) -> usize) -> Self {
+        let form = self.form.with_dims(new_dims);
+        let elems = form.elems();
+        let mut data = EcoVec::with_capacity(elems);
+        let mut index = Vec::with_capacity(form.dims_rank());
+        for i in 0..elems {
+            flat_to_dims(form.dims(), i, &mut index);
+            let mut j = 0;
+            for (pos, (&dim, &i)) in self.form.dims().iter().zip(&index).enumerate() {
+                j = j * dim + f(pos, i);
+            }
+            data.push(self.data[j].clone());
+        }
+        Array::new(form, data.into())
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn join(mut self, mut other: Self, rt: &Ufel) -> UfelResult<Self> {
+        let ori = rt.ori();
+        if self.form == other.form.row(ori) {
+            self.form.fix(ori);
+        }
+        if other.form == self.form.row(ori) {
+            other.form.fix(ori);
+        }
+        if self.form.vert_rank() != other.form.vert_rank()
+            || self.form.hori_rank() != other.form.hori_rank()
+        {
+            return Err(rt.error(format!(
+                "Cannot join arrays with forms {:?} and {:?} \
+                because their form matrices are {}×{} and {}×{}",
+                self.form,
+                other.form,
+                self.form.vert_rank(),
+                self.form.hori_rank(),
+                other.form.vert_rank(),
+                other.form.hori_rank()
+            )));
+        }
+        if self.form.dims()[1..] != other.form.dims()[1..] {
+            return Err(rt.error(format!(
+                "Cannot join arrays with forms {:?} and {:?} \
+                because their axes after the first differ",
+                self.form, other.form
+            )));
+        }
+        self.form[0][0] += other.form[0][0];
+        self.data.extend_from_cowslice(other.data);
+        self.validate_form();
+        Ok(self)
+    }
+    pub fn couple(self, other: Self, rt: &Ufel) -> UfelResult<Self> {
+        if self.form != other.form {
+            return Err(rt.error(format!(
+                "Cannot couple arrays with different forms {:?} and {:?}",
+                self.form, other.form
+            )));
+        }
+        Array::from_row_arrays([self, other], rt)
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn reshape(mut self, form: Array, rt: &Ufel) -> UfelResult<Self> {
+        let new_form = form.to_form(&format!("{:?} form", Dyadic::Reshape), rt)?;
+        let elems = new_form.elems();
+        if elems > self.data.len() {
+            if self.data.is_empty() {
+                return Err(rt.error(format!("Cannot reshape empty array to form {new_form:?}")));
+            }
+            self.data = self.data.iter().cycle().take(elems).cloned().collect();
+        } else {
+            self.data = self.data.slice(0..elems);
+        }
+        self.form = new_form;
+        self.validate_form();
+        Ok(self)
+    }
+}
+
+impl Array {
+    /// Interpret an array as a shape or a form matrix
+    pub(crate) fn to_form(&self, what: &str, rt: &Ufel) -> UfelResult<Form> {
+        if !self.form.is_normal() {
+            return Err(rt.error(format!(
+                "{what} must be normal, but its form is {:?}",
+                self.form
+            )));
+        }
+        for &d in &self.data {
+            if d.fract() != 0.0 || d < 0.0 {
+                return Err(rt.error(format!(
+                    "{what} must be all naturals, but one element is {d}"
+                )));
+            }
+        }
+        let dims: FormDims = self.data.iter().map(|&d| d as usize).collect();
+        let elems = (dims.iter()).try_fold(1usize, |acc, &d| acc.checked_mul(d));
+        if elems.is_none_or(|elems| elems > MAX_ARRAY_ELEMS) {
+            return Err(rt.error(format!(
+                "{what} {self} describes more than the maximum of {MAX_ARRAY_ELEMS} elements"
+            )));
+        }
+        Ok(match *self.form.shape(Ori::Hori) {
+            // Forms with no axes are all the same scalar form
+            _ if dims.is_empty() => Form::scalar(),
+            // A shape list always describes a normal form
+            [] | [_] => Form::from(dims),
+            [vert, hori] => Form::new(vert, hori, dims),
+            _ => {
+                return Err(rt.error(format!(
+                    "{what} must be a scalar, list, or form matrix, but its form is {:?}",
+                    self.form
+                )))
+            }
+        })
+    }
+    /// Interpret an array as a string of codepoints
+    pub(crate) fn to_str(&self, what: &str, rt: &Ufel) -> UfelResult<String> {
+        if self.form.hori_rank() > 1 || !self.form.is_normal() {
+            return Err(rt.error(format!(
+                "{what} must be a string, but its form is {:?}",
+                self.form
+            )));
+        }
+        (self.data.iter())
+            .map(|&d| {
+                (d.fract() == 0.0 && d >= 0.0)
+                    .then(|| char::from_u32(d as u32))
+                    .flatten()
+                    .ok_or_else(|| {
+                        rt.error(format!("{what} must be a string, but one element is {d}"))
+                    })
+            })
+            .collect()
+    }
+    /// Label the axes of an array's shape
+    ///
+    /// Labels are separated by whitespace, and `_` leaves an axis unlabeled
+    pub fn label(mut self, labels: Array, rt: &Ufel) -> UfelResult<Self> {
+        let labels = labels.to_str("Labels", rt)?;
+        let labels: Vec<&str> = labels.split_whitespace().collect();
+        let axes = self.form.shape_axes(rt.ori());
+        if labels.len() > axes.len() {
+            return Err(rt.error(format!(
+                "Cannot {:?} {} axes of {} shape {:?}",
+                Dyadic::Label,
+                labels.len(),
+                rt.ori().str(),
+                self.form.shape(rt.ori())
+            )));
+        }
+        for (axis, label) in axes.into_iter().zip(labels) {
+            let label = (label != "_").then(|| label.into());
+            self.form.set_label(axis, label);
+        }
+        // Labels must be unique to be found, including those already on other axes
+        let rank = self.form.dims_rank();
+        for axis in 0..rank {
+            let Some(label) = self.form.label(axis) else {
+                continue;
+            };
+            if (axis + 1..rank).any(|other| self.form.label(other) == Some(label)) {
+                return Err(rt.error(format!(
+                    "Cannot {:?} two axes with the same label {label}",
+                    Dyadic::Label
+                )));
+            }
+        }
+        Ok(self)
+    }
+    /// Get the form position of the axis with the given label
+    pub fn axis_of(&self, label: Array, rt: &Ufel) -> UfelResult<Array> {
+        let label = label.to_str("Label", rt)?;
+        let Some(axis) = self.form.find_label(&label) else {
+            return Err(rt.error(format!("Form {:?} has no axis labeled {label}", self.form)));
+        };
+        let hori = self.form.hori_rank();
+        Ok(Array::from([(axis / hori) as f64, (axis % hori) as f64]))
+    }
+    /// Create an array from data and a form with exactly as many elements
+    pub fn formed(self, form: Array, rt: &Ufel) -> UfelResult<Self> {
+        let form = form.to_form("Literal form", rt)?;
+        if form.elems() != self.data.len() {
+            return Err(rt.error(format!(
+                "Literal form {form:?} has {} elements, but the data has {}",
+                form.elems(),
+                self.data.len()
+            )));
+        }
+        Ok(Array::new(form, self.data))
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn member(self, of: Self, rt: &Ufel) -> UfelResult<Array> {
+        self.find_rows(of, Dyadic::Member, |i| i.is_some() as u8 as f64, rt)
+    }
+    pub fn index_of(self, of: Self, rt: &Ufel) -> UfelResult<Array> {
+        let len = of.form.row_count(rt.ori());
+        self.find_rows(of, Dyadic::IndexOf, |i| i.unwrap_or(len) as f64, rt)
+    }
+    /// Look up this array or each of its rows among the rows of another array
+    fn find_rows(
+        self,
+        of: Self,
+        prim: Dyadic,
+        f: impl Fn(Option<usize>) -> f64,
+        rt: &Ufel,
+    ) -> UfelResult<Array> {
+        let ori = rt.ori();
+        let row_form = of.form.row(ori);
+        let mut indices = HashMap::with_capacity(of.form.row_count(ori));
+        for (i, row) in of.into_rows(ori).enumerate() {
+            indices.entry(row).or_insert(i);
+        }
+        if self.form == row_form {
+            Ok(f(indices.get(&self).copied()).into())
+        } else if self.form.row(ori) == row_form {
+            Ok((self.into_rows(ori))
+                .map(|row| f(indices.get(&row).copied()))
+                .collect())
+        } else {
+            Err(rt.error(format!(
+                "{prim:?} cannot look for an array of form {:?} \
+                among {} rows of form {row_form:?}",
+                self.form,
+                ori.str()
+            )))
+        }
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn keep(self, counts: Array, rt: &Ufel) -> UfelResult<Self> {
+        let ori = rt.ori();
+        if !counts.form.is_normal() || counts.form.hori_rank() > 1 {
+            return Err(rt.error(format!(
+                "{:?} counts must be a scalar or list, but their form is {:?}",
+                Dyadic::Keep,
+                counts.form
+            )));
+        }
+        for &n in &counts.data {
+            if n.fract() != 0.0 || n < 0.0 {
+                return Err(rt.error(format!(
+                    "{:?} counts must be all naturals, but one element is {n}",
+                    Dyadic::Keep
+                )));
+            }
+        }
+        let row_count = self.form.row_count(ori);
+        let row_len = self.form.row_len(ori);
+        let counts: Vec<usize> = if counts.form.is_scalar() {
+            vec![counts.data[0] as usize; row_count]
+        } else if counts.data.len() == row_count {
+            counts.data.iter().map(|&n| n as usize).collect()
+        } else {
+            return Err(rt.error(format!(
+                "Cannot {:?} array with {row_count} {} rows using {} counts",
+                Dyadic::Keep,
+                ori.str(),
+                counts.data.len()
+            )));
+        };
+        let data = self.row_data(ori);
+        let mut kept = EcoVec::with_capacity(counts.iter().sum::<usize>() * row_len);
+        for (i, &n) in counts.iter().enumerate() {
+            for _ in 0..n {
+                kept.extend_from_slice(&data[i * row_len..(i + 1) * row_len]);
+            }
+        }
+        let mut form = self.form.row(ori);
+        form.fix(ori);
+        form[0][0] = counts.iter().sum();
+        Ok(Array::new(form, kept.into()))
+    }
+}
+
+impl<T: Element> Array<T> {
+    pub fn windows(self, spec: Array, rt: &Ufel) -> UfelResult<Self> {
+        self.windows_impl(spec, rt).map(|(arr, _)| arr)
+    }
+    /// Get the windows of an array along with the number of form rows or columns that count the windows
+    ///
+    /// Window extents along horizontal form rows are placed in new form rows,
+    /// and extents along vertical form columns are placed in new form columns.
+    pub(crate) fn windows_impl(mut self, spec: Array, rt: &Ufel) -> UfelResult<(Self, usize)> {
+        let ori = rt.ori();
+        if !spec.form.is_normal() || spec.form.hori_rank() > 2 {
+            return Err(rt.error(format!(
+                "Window size must be a scalar, list, or form matrix, but its form is {:?}",
+                spec.form
+            )));
+        }
+        for &size in &spec.data {
+            if size.fract() != 0.0 || size < 1.0 {
+                return Err(rt.error(format!(
+                    "Window size must be all positive integers, but one element is {size}"
+                )));
+            }
+        }
+        let (vert, hori) = (self.form.vert_rank(), self.form.hori_rank());
+        // Get the spec as a matrix in the current orientation
+        let (spec_vert, spec_hori) = match (spec.form.shape(Ori::Hori).as_ref(), ori) {
+            ([], _) => (1, 1),
+            (&[n], Ori::Hori) => (1, n),
+            (&[n], Ori::Vert) => (n, 1),
+            (&[v, h], _) => (v, h),
+            _ => unreachable!(),
+        };
+        if spec_vert > vert || spec_hori > hori {
+            return Err(rt.error(format!(
+                "Window size has too many axes for form {:?}",
+                self.form
+            )));
+        }
+        let mut sizes = vec![1; vert * hori];
+        for (i, &size) in spec.data.iter().enumerate() {
+            sizes[i / spec_hori * hori + i % spec_hori] = size as usize;
+        }
+        // The number of windowed form rows or columns
+        let k = match ori {
+            Ori::Hori => spec_vert,
+            Ori::Vert => spec_hori,
+        };
+        let windowed = |pos: usize| match ori {
+            Ori::Hori => pos / hori < k,
+            Ori::Vert => pos % hori < k,
+        };
+        let (new_vert, new_hori) = match ori {
+            Ori::Hori => (vert + k, hori),
+            Ori::Vert => (vert, hori + k),
+        };
+        // Build the data with each window axis directly after its count axis
+        let mut new_dims = FormDims::with_capacity(new_vert * new_hori);
+        let mut dests = Vec::with_capacity(new_vert * new_hori);
+        for (pos, &dim) in self.form.dims().iter().enumerate() {
+            let (r, c) = (pos / hori, pos % hori);
+            // Window and unwindowed axes are shifted past the count axes
+            let count_dest = r * new_hori + c;
+            let dest = match ori {
+                Ori::Hori => (r + k) * new_hori + c,
+                Ori::Vert => r * new_hori + k + c,
+            };
+            if windowed(pos) {
+                let size = sizes[pos];
+                if size > dim {
+                    return Err(rt.error(format!(
+                        "Window size {size} is too large for axis {pos} size {dim}"
+                    )));
+                }
+                new_dims.push(dim - size + 1);
+                dests.push(count_dest);
+                new_dims.push(size);
+                dests.push(dest);
+            } else {
+                new_dims.push(dim);
+                dests.push(dest);
+            }
+        }
+        let elems: usize = new_dims.iter().product();
+        let mut data = EcoVec::with_capacity(elems);
+        let mut index = Vec::with_capacity(new_dims.len());
+        for i in 0..elems {
+            flat_to_dims(&new_dims, i, &mut index);
+            let mut index = index.iter();
+            let mut j = 0;
+            for (pos, &dim) in self.form.dims().iter().enumerate() {
+                let mut axis_i = *index.next().unwrap();
+                if windowed(pos) {
+                    axis_i += *index.next().unwrap();
+                }
+                j = j * dim + axis_i;
+            }
+            data.push(self.data[j].clone());
+        }
+        self.form = Form::new(new_vert, new_hori, new_dims);
+        self.data = data.into();
+        self.validate_form();
+        Ok((self.move_axes(&dests, rt)?, k))
+    }
+}
+
+fn axis_counts(counts: &Array, rank: usize, prim: Dyadic, rt: &Ufel) -> UfelResult<Vec<isize>> {
+    if !counts.form.is_normal() {
+        return Err(rt.error(format!(
+            "{prim:?} counts must be normal, but its form is {:?}",
+            counts.form
+        )));
+    }
+    if counts.form.hori_rank() > 1 {
+        return Err(rt.error(format!(
+            "{prim:?} counts must be a scalar or list, but its form is {:?}",
+            counts.form
+        )));
+    }
+    let mut res = Vec::with_capacity(counts.data.len());
+    for &n in &counts.data {
+        if n.fract() != 0.0 {
+            return Err(rt.error(format!(
+                "{prim:?} counts must be all integers, but one element is {n}"
+            )));
+        }
+        res.push(n as isize);
+    }
+    if res.len() > rank {
+        return Err(rt.error(format!(
+            "{prim:?} counts have too many axes for {} rank {rank}",
+            rt.ori().str(),
+        )));
+    }
+    Ok(res)
+}
+
+fn normalize_index(i: f64, len: usize, prim: Dyadic, ori: Ori, rt: &Ufel) -> UfelResult<usize> {
+    if i.fract() != 0.0 {
+        return Err(rt.error(format!(
+            "{prim:?} indices must be all integers, but one element is {i}"
+        )));
+    }
+    let abs_i = (i as isize).unsigned_abs();
+    let norm = if i >= 0.0 {
+        Some(abs_i)
+    } else {
+        len.checked_sub(abs_i)
+    };
+    match norm {
+        Some(norm) if norm < len => Ok(norm),
+        _ => Err(rt.error(format!(
+            "Index {i} is out of bounds of {} length {len}",
+            ori.str()
+        ))),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn select_and_pick() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3] 1S"), test_run("2"));
+    assert_eq!(test_run("[1 2 3] `1S"), test_run("3"));
+    assert_eq!(test_run("[[1 2] [3 4]] [1 0]S"), test_run("[[3 4] [1 2]]"));
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [[1 0] [0 1]]S"),
+        test_run("[[[3 4] [1 2]] [[1 2] [3 4]]]")
+    );
+    assert_eq!(test_run("[[1 2] [3 4]] [1 0]P"), test_run("3"));
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [2 2]i P"),
+        test_run("[[1 2] [3 4]]")
+    );
+    assert!(test_error("[1 2 3] 3S").contains("Index 3 is out of bounds of horizontal length 3"));
+    assert!(test_error("[1 2 3] `4S").contains("out of bounds"));
+    assert!(test_error("[1 2 3] 1.5S").contains("indices must be all integers"));
+    assert!(test_error("[[1 2] [3 4]] [2 0]P").contains("out of bounds"));
+}
+
+#[cfg(test)]
+#[test]
+fn take_drop_rotate() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3 4] 2T"), test_run("[1 2]"));
+    assert_eq!(test_run("[1 2 3 4] `2T"), test_run("[3 4]"));
+    assert_eq!(test_run("[[1 2] [3 4]] [1 1]T"), test_run("[[1]]"));
+    assert_eq!(test_run("6i [2 3] H ~(1T)"), test_run("[[0 1 2]]"));
+    assert_eq!(test_run("[1 2 3 4] 1D"), test_run("[2 3 4]"));
+    assert_eq!(test_run("[1 2 3 4] `1D"), test_run("[1 2 3]"));
+    assert_eq!(test_run("[1 2 3 4] 9D"), test_run("[]"));
+    assert_eq!(test_run("[1 2 3 4] 1R"), test_run("[2 3 4 1]"));
+    assert_eq!(test_run("[1 2 3 4] `1R"), test_run("[4 1 2 3]"));
+    assert!(test_error("[1 2 3 4] 5T").contains("Cannot take 5 from horizontal axis 0 of length 4"));
+    assert!(test_error("[1 2 3 4] 1.5T").contains("counts must be all integers"));
+    assert!(test_error("[1 2 3] [1 1]T").contains("too many axes for horizontal rank 1"));
+}
+
+#[cfg(test)]
+#[test]
+fn join_and_couple() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2] [3 4]J"), test_run("[1 2 3 4]"));
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [5 6]J"),
+        test_run("[[1 2] [3 4] [5 6]]")
+    );
+    assert_eq!(test_run("[1 2] 3J"), test_run("[1 2 3]"));
+    assert_eq!(test_run("[1 2] [3 4]K"), test_run("[[1 2] [3 4]]"));
+    assert!(test_error("[[1 2] [3 4]] [5 6] ~J").contains(
+        "Cannot join arrays with forms [2×2] and [2] because their form matrices are 1×2 and 1×1"
+    ));
+    assert!(test_error("[[1 2] [3 4]] [[1 2 3]]J").contains(
+        "Cannot join arrays with forms [2×2] and [1×3] because their axes after the first differ"
+    ));
+    assert!(test_error("[1 2] [1 2 3]K").contains("Cannot couple arrays with different forms"));
+}
+
+#[cfg(test)]
+#[test]
+fn reshape_and_deshape() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("6i [2 3]H"), test_run("[[0 1 2] [3 4 5]]"));
+    assert_eq!(test_run("3i [2 2]H"), test_run("[[0 1] [2 0]]"));
+    assert_eq!(test_run("[[1 2] [3 4]] f"), test_run("[1 2 3 4]"));
+    // An empty shape is the scalar form, and a shape list is always normal
+    assert_eq!(test_run("[5 6] [] H 5 B"), test_run("1"));
+    assert_eq!(test_run("6i [2 3] ~H m"), test_run("[[2 3]]"));
+    assert_eq!(test_run("6i [2 3] ~H"), test_run("6i [2 3] H"));
+    assert!(test_error("[] [2]H").contains("Cannot reshape empty array"));
+    assert!(test_error("5i 1e30 H").contains("describes more than the maximum"));
+    assert!(test_error("5i [1e10 1e10 1e10]H").contains("describes more than the maximum"));
+    assert!(test_error("5i [2 1.5]H").contains("must be all naturals"));
+}
+
+#[cfg(test)]
+#[test]
+fn member_and_index_of() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("2 [1 2 3] B"), test_run("1"));
+    assert_eq!(test_run("[2 5] [1 2 3] B"), test_run("[1 0]"));
+    assert_eq!(test_run("[1 2] [[1 2] [3 4]] B"), test_run("1"));
+    assert_eq!(test_run("2 [1 2 3] I"), test_run("1"));
+    assert_eq!(test_run("[3 5] [1 2 3] I"), test_run("[2 3]"));
+    assert_eq!(
+        test_run("{[0 1 2] [[1] [3]]} {[3 4 5 0 1 2] [[2] [3]]} ~I"),
+        test_run("[1]")
+    );
+    assert!(test_error("[1 2 3] [[1 2] [3 4]] B").contains("(B)member"));
+}
+
+#[cfg(test)]
+#[test]
+fn keep() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3] [1 0 2] F"), test_run("[1 3 3]"));
+    assert_eq!(test_run("[1 2 3] 2 F"), test_run("[1 1 2 2 3 3]"));
+    assert_eq!(test_run("[1 2 3] :2L F"), test_run("[1]"));
+    assert_eq!(
+        test_run("{[3 4 5 0 1 2] [[2] [3]]} [0 1] ~F"),
+        test_run("{[0 1 2] [[1] [3]]}")
+    );
+    assert!(test_error("[1 2 3] [1 0] F")
+        .contains("Cannot (F)keep array with 3 horizontal rows using 2 counts"));
+    assert!(test_error("[1 2 3] [1 `1 0] F").contains("counts must be all naturals"));
+}
+
+#[cfg(test)]
+#[test]
+fn form_literal() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("{6i [2 3]}"), test_run("6i [2 3]H"));
+    assert_eq!(test_run("{[1 2 3] 3}"), test_run("[1 2 3]"));
+    assert_eq!(test_run("{6i [[3] [2]]} m"), test_run("[[3] [2]]"));
+    assert_eq!(test_run("{6i [[3] [2]]} ~n"), test_run("3"));
+    assert_eq!(test_run("{[] [[0] [2]]} m"), test_run("[[0] [2]]"));
+    assert_eq!(test_run("{5 []} 5 K"), test_run("[5 5]"));
+    assert_eq!(test_run("{[5] []}"), test_run("5"));
+    assert_eq!(test_run("~({6i [2 3]})"), test_run("6i [2 3] H"));
+    assert!(test_error("{6i [[3] [3]]}")
+        .contains("Literal form [×3 ×3] has 9 elements, but the data has 6"));
+    assert!(test_error("{6i [[3] [2.5]]}")
+        .contains("Literal form must be all naturals, but one element is 2.5"));
+    assert!(test_error("{6i}")
+        .contains("Form literal must contain data and a form, but its signature is |0.1"));
+}
+
+#[cfg(test)]
+#[test]
+fn labels() {
+    use crate::{test_error, test_run};
+    let form = |code: &str| format!("{:?}", test_run(code)[0].form);
+    let labeled = "6i [2 3] H @\"row col\" @label ";
+    assert_eq!(form(labeled), "[row:2×col:3]");
+    assert_eq!(
+        test_run(&format!("{labeled}@\"col\" @axisof")),
+        test_run("[0 1]")
+    );
+    assert_eq!(form(&format!("{labeled}@\"_ c\" @label")), "[2×c:3]");
+    // Labels follow their axes
+    assert_eq!(form(&format!("{labeled}1T")), "[row:1×col:3]");
+    assert_eq!(form(&format!("{labeled}[1 1]R")), "[row:2×col:3]");
+    assert_eq!(form(&format!("{labeled}t")), "[col:3×row:2]");
+    assert_eq!(form(&format!("{labeled}w")), "[×row:2 ×col:3]");
+    assert_eq!(form("12i @\"x\" @label 3C"), "[×x:4 ×3]");
+    assert_eq!(
+        form("[1 2 3] @\"x\" @label [10 20] @\"y\" @label O+"),
+        "[×x:3 ×y:2]"
+    );
+    let cube = "24i [2 3 4] H @\"a b c\" @label ";
+    assert_eq!(form(&format!("{cube}2 @axis(.)")), "[a:2×b:3×c:4]");
+    assert_eq!(form(&format!("{cube}2 @axis(r+)")), "[a:2×b:3]");
+    assert!(test_error("6i [2 3] H @\"a a\" @label")
+        .contains("Cannot @label two axes with the same label a"));
+    assert!(test_error(&format!("{labeled}@\"col\" @label"))
+        .contains("Cannot @label two axes with the same label col"));
+    assert!(test_error(&format!("{labeled}@\"x\" @axisof")).contains("has no axis labeled x"));
+}
@@ -2,7 +2,7 @@ use std::{error::Error, fmt, mem::take};
 
 use ecow::{EcoString, EcoVec};
 
-use crate::{HumanSp, HumanSpan, ParseError};
+use crate::{Array, HumanSp, HumanSpan, ParseError};
 
 #[derive(Debug, Clone)]
 pub struct UfelError {
@@ -30,6 +30,8 @@ pub enum UfelErrorKind {
     Parse(HumanSp<ParseError>),
     Compile(LocatedError),
     Run(LocatedError),
+    /// An error raised by [`Dyadic::Assert`](crate::Dyadic::Assert) with its message value
+    Assert(LocatedError, Box<Array>),
 }
 
 impl fmt::Display for UfelError {
@@ -38,6 +40,7 @@ impl fmt::Display for UfelError {
             UfelErrorKind::Parse(e) => write!(f, "Parse error at {e}"),
             UfelErrorKind::Compile(e) => write!(f, "Compile error at {e}"),
             UfelErrorKind::Run(e) => write!(f, "Runtime error at {e}"),
+            UfelErrorKind::Assert(e, _) => write!(f, "Assertion failed at {e}"),
         }
     }
 }
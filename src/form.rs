@@ -1,10 +1,12 @@
 use std::{
     borrow::Cow,
     fmt,
+    hash::{Hash, Hasher},
     mem::swap,
     ops::{Deref, Index, IndexMut, Not},
 };
 
+use ecow::{EcoString, EcoVec};
 use tinyvec::{tiny_vec, TinyVec};
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -12,22 +14,66 @@ pub struct Form {
     vert: usize,
     hori: usize,
     dims: FormDims,
+    labels: AxisLabels,
 }
 
 pub type FormDims = TinyVec<[usize; 3]>;
 
+/// Optional names for the axes of a [`Form`], parallel to its dims
+///
+/// Labels are only metadata, so they never affect comparisons between forms
+#[derive(Clone, Default)]
+struct AxisLabels(Option<EcoVec<Option<EcoString>>>);
+
+impl PartialEq for AxisLabels {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for AxisLabels {}
+
+impl PartialOrd for AxisLabels {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AxisLabels {
+    fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl Hash for AxisLabels {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
 impl Form {
     #[track_caller]
     pub fn new(vert: usize, hori: usize, dims: FormDims) -> Self {
-        let form = Self { vert, hori, dims };
+        let form = Self {
+            vert,
+            hori,
+            dims,
+            labels: AxisLabels::default(),
+        };
         form.validate();
         form
     }
+    /// Get a form with the same layout and labels but different dims
+    #[track_caller]
+    pub(crate) fn with_dims(&self, dims: FormDims) -> Self {
+        let mut form = Self::new(self.vert, self.hori, dims);
+        form.labels = self.labels.clone();
+        form
+    }
     pub fn scalar() -> Self {
         Self {
             vert: 0,
             hori: 0,
             dims: TinyVec::new(),
+            labels: AxisLabels::default(),
         }
     }
     pub fn empty_list() -> Self {
@@ -35,6 +81,7 @@ impl Form {
             vert: 1,
             hori: 1,
             dims: tiny_vec![0],
+            labels: AxisLabels::default(),
         }
     }
     pub fn elems(&self) -> usize {
@@ -59,7 +106,7 @@ impl Form {
             Ori::Vert => self.hori_axis_rows().skip(1).flatten().product(),
         }
     }
-    pub fn shape(&self, ori: Ori) -> Shape {
+    pub fn shape(&self, ori: Ori) -> Shape<'_> {
         match ori {
             Ori::Hori => Shape(self.hori_axis_rows().next().unwrap_or(&[]).into()),
             Ori::Vert => Shape(
@@ -71,6 +118,16 @@ impl Form {
             ),
         }
     }
+    /// Get the indices into [`Form::dims`] of the axes in [`Form::shape`]
+    pub fn shape_axes(&self, ori: Ori) -> Vec<usize> {
+        if self.is_scalar() {
+            return Vec::new();
+        }
+        match ori {
+            Ori::Hori => (0..self.hori).collect(),
+            Ori::Vert => (0..self.vert).map(|i| i * self.hori).collect(),
+        }
+    }
     pub fn is_scalar(&self) -> bool {
         self.vert == 0 || self.hori == 0
     }
@@ -109,6 +166,10 @@ impl Form {
     pub fn normal_rank(&self) -> Option<usize> {
         self.is_normal().then(|| self.hori_rank())
     }
+    /// Get the form of each row in the given orientation
+    ///
+    /// If removing the leading axes leaves no form rows or columns,
+    /// the rows are scalars
     pub fn row(&self, ori: Ori) -> Self {
         let (vert, hori, dims) = match ori {
             Ori::Hori => {
@@ -134,10 +195,53 @@ impl Form {
                 (vert, hori, dims)
             }
         };
-        let form = Self { vert, hori, dims };
+        if vert == 0 || hori == 0 {
+            return Self::scalar();
+        }
+        let mut form = Self {
+            vert,
+            hori,
+            dims,
+            labels: AxisLabels::default(),
+        };
         form.validate();
+        form.relabel(
+            self,
+            (0..vert * hori).map(|k| match ori {
+                Ori::Hori => Some(k / hori * self.hori + k % hori + 1),
+                Ori::Vert => Some(k + self.hori),
+            }),
+        );
         form
     }
+    /// Get the data indices of an array's elements in row order for the given orientation
+    ///
+    /// Returns `None` if the rows are already contiguous
+    pub fn row_order(&self, ori: Ori) -> Option<Vec<usize>> {
+        if ori == Ori::Vert || self.vert <= 1 || self.hori <= 1 {
+            return None;
+        }
+        let row_len = self.row_len(ori);
+        let mut order = vec![0; self.elems()];
+        let mut index = vec![0; self.dims.len()];
+        for flat in 0..self.elems() {
+            let mut rem = flat;
+            for (k, &dim) in self.dims.iter().enumerate().rev() {
+                index[k] = rem % dim;
+                rem /= dim;
+            }
+            let (mut row, mut col) = (0, 0);
+            for (k, &dim) in self.dims.iter().enumerate() {
+                if k % self.hori == 0 {
+                    row = row * dim + index[k];
+                } else {
+                    col = col * dim + index[k];
+                }
+            }
+            order[row * row_len + col] = flat;
+        }
+        Some(order)
+    }
     pub fn hori_axis_rows(&self) -> impl DoubleEndedIterator<Item = &[usize]> {
         self.dims.chunks_exact(self.hori.max(1))
     }
@@ -154,6 +258,19 @@ impl Form {
         }
         true
     }
+    /// Get the form this form must be filled to in order to match another
+    ///
+    /// Each axis that is at the same form position in both forms is
+    /// extended to the longer of the two lengths
+    pub fn fill_target(&self, other: &Self) -> Self {
+        let mut target = self.clone();
+        for i in 0..self.vert.min(other.vert) {
+            for j in 0..self.hori.min(other.hori) {
+                target[i][j] = target[i][j].max(other[i][j]);
+            }
+        }
+        target
+    }
     pub fn prefixes_match(&self, other: &Self) -> bool {
         self.is_prefix_of(other) || other.is_prefix_of(self)
     }
@@ -164,40 +281,59 @@ impl Form {
                 dims.push(self.dims[i * self.hori + j]);
             }
         }
+        self.dims = dims;
+        let (vert, hori) = (self.vert, self.hori);
         swap(&mut self.vert, &mut self.hori);
+        let from = self.clone();
+        self.relabel(
+            &from,
+            (0..vert * hori).map(|k| Some((k % vert) * hori + k / vert)),
+        );
     }
+    /// Add a leading unit axis in the given orientation
+    ///
+    /// Horizontally, each form row gets a new first column.
+    /// Vertically, the form gets a new first row.
+    /// A scalar becomes a list of length 1 in either orientation.
     pub fn fix(&mut self, ori: Ori) {
+        if self.is_scalar() {
+            *self = Form::from([1]);
+            return;
+        }
         let new_vert = (self.vert + (ori == Ori::Vert) as usize).max(1);
         let new_hori = (self.hori + (ori == Ori::Hori) as usize).max(1);
         let mut dims = TinyVec::with_capacity(new_vert * new_hori);
-        if self.is_scalar() {
-            dims.push(1);
-        } else {
-            match ori {
-                Ori::Hori => {
-                    for i in 0..self.vert {
-                        dims.push(1);
-                        for j in 0..self.hori {
-                            dims.push(self[i][j]);
-                        }
+        let mut sources = Vec::with_capacity(new_vert * new_hori);
+        match ori {
+            Ori::Hori => {
+                for i in 0..self.vert {
+                    dims.push(1);
+                    sources.push(None);
+                    for j in 0..self.hori {
+                        dims.push(self[i][j]);
+                        sources.push(Some(i * self.hori + j));
                     }
                 }
-                Ori::Vert => {
-                    for i in 0..self.vert {
-                        for _ in 0..self.hori {
-                            dims.push(1);
-                        }
-                        for j in 0..self.hori {
-                            dims.push(self[i][j]);
-                        }
+            }
+            Ori::Vert => {
+                for _ in 0..self.hori {
+                    dims.push(1);
+                    sources.push(None);
+                }
+                for i in 0..self.vert {
+                    for j in 0..self.hori {
+                        dims.push(self[i][j]);
+                        sources.push(Some(i * self.hori + j));
                     }
                 }
             }
         }
+        let from = self.clone();
         self.dims = dims;
         self.vert = new_vert;
         self.hori = new_hori;
         self.validate();
+        self.relabel(&from, sources);
     }
     pub fn deform(&mut self, ori: Ori) {
         match ori {
@@ -212,6 +348,48 @@ impl Form {
         }
         self.validate();
     }
+    /// Get the label of an axis, indexed into [`Form::dims`]
+    pub fn label(&self, axis: usize) -> Option<&str> {
+        self.labels.0.as_ref()?.get(axis)?.as_deref()
+    }
+    pub fn has_labels(&self) -> bool {
+        self.labels.0.is_some()
+    }
+    /// Set the label of an axis, indexed into [`Form::dims`]
+    pub fn set_label(&mut self, axis: usize, label: Option<EcoString>) {
+        let rank = self.dims_rank();
+        let labels = self
+            .labels
+            .0
+            .get_or_insert_with(|| (0..rank).map(|_| None).collect());
+        labels.make_mut()[axis] = label;
+        if labels.iter().all(Option::is_none) {
+            self.labels.0 = None;
+        }
+    }
+    /// Find the axis with the given label, indexed into [`Form::dims`]
+    pub fn find_label(&self, label: &str) -> Option<usize> {
+        (self.labels.0.as_ref()?.iter()).position(|l| l.as_deref() == Some(label))
+    }
+    /// Take labels from another form
+    ///
+    /// Each axis takes the label of the axis of `from` at its source index
+    pub(crate) fn relabel(
+        &mut self,
+        from: &Form,
+        sources: impl IntoIterator<Item = Option<usize>>,
+    ) {
+        self.labels.0 = None;
+        if !from.has_labels() {
+            return;
+        }
+        for (axis, src) in sources.into_iter().enumerate().take(self.dims_rank()) {
+            let label = src.and_then(|j| from.label(j)).map(Into::into);
+            if label.is_some() {
+                self.set_label(axis, label);
+            }
+        }
+    }
     pub fn rerank(&mut self, _rank: usize) {
         todo!()
     }
@@ -265,6 +443,7 @@ impl<const N: usize> From<[usize; N]> for Form {
             vert: 1,
             hori: N,
             dims: dims.into_iter().collect(),
+            labels: AxisLabels::default(),
         }
     }
 }
@@ -275,6 +454,7 @@ impl From<&[usize]> for Form {
             vert: 1,
             hori: dims.len(),
             dims: dims.iter().copied().collect(),
+            labels: AxisLabels::default(),
         }
     }
 }
@@ -285,6 +465,7 @@ impl<const M: usize, const N: usize> From<[[usize; N]; M]> for Form {
             vert: M,
             hori: N,
             dims: dims.into_iter().flatten().collect(),
+            labels: AxisLabels::default(),
         }
     }
 }
@@ -295,6 +476,7 @@ impl From<FormDims> for Form {
             vert: 1,
             hori: dims.len(),
             dims,
+            labels: AxisLabels::default(),
         }
     }
 }
@@ -309,12 +491,18 @@ impl FromIterator<usize> for Form {
 impl fmt::Debug for Form {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
+        let write_dim = |f: &mut fmt::Formatter<'_>, axis: usize| {
+            if let Some(label) = self.label(axis) {
+                write!(f, "{label}:")?;
+            }
+            write!(f, "{}", self.dims[axis])
+        };
         if let Some(dims) = self.as_normal() {
-            for (i, dim) in dims.iter().enumerate() {
+            for i in 0..dims.len() {
                 if i > 0 {
                     write!(f, "×")?;
                 }
-                write!(f, "{}", dim)?;
+                write_dim(f, i)?;
             }
         } else {
             for i in 0..self.vert {
@@ -328,7 +516,7 @@ impl fmt::Debug for Form {
                     if j > 0 || self.hori == 1 {
                         write!(f, "×")?;
                     }
-                    write!(f, "{}", self[i][j])?;
+                    write_dim(f, i * self.hori + j)?;
                 }
             }
         }
@@ -384,3 +572,26 @@ impl Not for Ori {
         }
     }
 }
+
+#[test]
+fn form_row() {
+    let form = Form::from([[2, 3], [4, 5]]);
+    assert_eq!(form.row(Ori::Hori), Form::from([[3], [5]]));
+    assert_eq!(form.row(Ori::Vert), Form::from([[4, 5]]));
+    assert_eq!(Form::from([3]).row(Ori::Hori), Form::scalar());
+    assert_eq!(Form::from([3]).row(Ori::Vert), Form::scalar());
+    assert_eq!(Form::from([[3], [4]]).row(Ori::Hori), Form::scalar());
+}
+
+#[test]
+fn form_fix() {
+    let mut form = Form::scalar();
+    form.fix(Ori::Vert);
+    assert_eq!(form, Form::from([1]));
+    let mut form = Form::from([[2, 3], [4, 5]]);
+    form.fix(Ori::Hori);
+    assert_eq!(form, Form::from([[1, 2, 3], [1, 4, 5]]));
+    let mut form = Form::from([[2, 3], [4, 5]]);
+    form.fix(Ori::Vert);
+    assert_eq!(form, Form::from([[1, 1], [2, 3], [4, 5]]));
+}
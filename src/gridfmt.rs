@@ -6,8 +6,14 @@ impl Array {
             return self.data[0].to_string();
         }
         let elem_strs: Vec<String> = self.data.iter().map(|x| x.to_string()).collect();
+        let labels = self.form.has_labels().then(|| format!("{:?}", self.form));
         if self.form.dims_rank() == 1 {
-            let mut s = String::from('[');
+            let mut s = String::new();
+            if let Some(label) = self.form.label(0) {
+                s.push_str(label);
+                s.push(':');
+            }
+            s.push('[');
             for (i, elem) in elem_strs.iter().enumerate() {
                 if i > 0 {
                     s.push(' ');
@@ -23,6 +29,9 @@ impl Array {
             max_widths[i % last_dim] = max_widths[i % last_dim].max(s.len());
         }
         let mut width = max_widths.iter().sum::<usize>() + last_dim.saturating_sub(1) + 5;
+        if let Some(labels) = &labels {
+            width = width.max(labels.chars().count() + 3);
+        }
         let mut overflow = false;
         if let Some((w, _)) = terminal_size::terminal_size() {
             let w = w.0 as usize;
@@ -87,6 +96,11 @@ impl Array {
         }
         grid[0] = '╭';
         grid[1] = '─';
+        if let Some(labels) = labels {
+            for (c, cell) in labels.chars().zip(&mut grid[2..width - 1]) {
+                *cell = c;
+            }
+        }
         for i in 1..self.form.dims_rank() {
             grid[i * width] = '╷';
         }
@@ -95,3 +109,14 @@ impl Array {
         grid.into_iter().collect()
     }
 }
+
+#[cfg(test)]
+#[test]
+fn show_labels() {
+    let show = |code: &str| crate::test_run(code)[0].show();
+    assert_eq!(show("[1 2 3] @\"x\" @label"), "x:[1 2 3]");
+    assert_eq!(
+        show("6i [2 3] H @\"row col\" @label"),
+        "╭─[row:2×col:3]\n╷ 0 1 2        \n  3 4 5        \n              ╯"
+    );
+}
@@ -0,0 +1,86 @@
+use ecow::EcoVec;
+
+use crate::{Dyadic, ImplPrim, Mod, Monadic, Node, SigNode, Signature};
+
+impl Node {
+    /// Get the inverse of a node
+    ///
+    /// On failure, returns the first node found that has no inverse
+    ///
+    /// A chunk can only be inverted if its size is computed
+    /// inside the function without taking any arguments
+    pub(crate) fn un(&self) -> Result<Node, &Node> {
+        Ok(match self {
+            Node::Run(nodes) => {
+                let mut inverted = EcoVec::with_capacity(nodes.len());
+                let mut rest = nodes.as_slice();
+                while let Some((last, init)) = rest.split_last() {
+                    match (init.last(), last) {
+                        // Unchunking does not need the chunk size,
+                        // so the nodes that compute it are dropped
+                        (Some(_), Node::Dy(Dyadic::Chunk, span)) => {
+                            let size_len = (1..=init.len())
+                                .find(|&k| Node::from(&init[init.len() - k..]).sig() == (0, 1))
+                                .ok_or(last)?;
+                            inverted.push(Node::Mon(Monadic::Unchunk, *span));
+                            rest = &init[..init.len() - size_len];
+                        }
+                        (Some(Node::Push(val)), Node::Dy(prim, span)) => {
+                            inverted.push(un_dyadic(val, *prim, *span).ok_or(last)?);
+                            rest = &init[..init.len() - 1];
+                        }
+                        _ => {
+                            inverted.push(last.un()?);
+                            rest = init;
+                        }
+                    }
+                }
+                Node::Run(inverted)
+            }
+            Node::Mon(prim, span) => match prim {
+                Monadic::Identity
+                | Monadic::Neg
+                | Monadic::Not
+                | Monadic::Reverse
+                | Monadic::Swap => self.clone(),
+                Monadic::Transpose => Node::Impl(ImplPrim::Untranspose, *span),
+                _ => return Err(self),
+            },
+            Node::Impl(ImplPrim::Untranspose, span) => Node::Mon(Monadic::Transpose, *span),
+            Node::Mod(prim @ (Mod::Turn | Mod::Horizontal | Mod::Vertical | Mod::Dip), f, span) => {
+                let sig = Signature::new(f.sig.outputs, f.sig.args);
+                Node::Mod(*prim, SigNode::new(f.node.un()?, sig).into(), *span)
+            }
+            _ => return Err(self),
+        })
+    }
+}
+
+/// Get the inverse of a dyadic function called with a constant
+fn un_dyadic(val: &crate::Array, prim: Dyadic, span: usize) -> Option<Node> {
+    let push = || Node::Push(val.clone());
+    Some(match prim {
+        Dyadic::Add => Node::from_iter([push(), Node::Dy(Dyadic::Sub, span)]),
+        Dyadic::Sub => Node::from_iter([push(), Node::Dy(Dyadic::Add, span)]),
+        Dyadic::Mul => Node::from_iter([push(), Node::Dy(Dyadic::Div, span)]),
+        Dyadic::Div => Node::from_iter([push(), Node::Dy(Dyadic::Mul, span)]),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn un_and_under() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3] V(1+)"), test_run("[0 1 2]"));
+    assert_eq!(test_run("[2 4 6] V(2*)"), test_run("[1 2 3]"));
+    assert_eq!(test_run("12i [3 4]H t Vt m"), test_run("[[3 4]]"));
+    assert_eq!(test_run("12i 3C V(3C)"), test_run("12i"));
+    // The chunk size may be computed
+    assert_eq!(test_run("12i 3C V(2 1+ C)"), test_run("12i"));
+    assert_eq!(test_run("12i U(3C)(1+)"), test_run("12i 1+"));
+    assert_eq!(test_run("[1 2 3] U(1+)(2*)"), test_run("[3 5 7]"));
+    assert!(test_error("12i 4C V(C)").contains("(C)chunk has no inverse"));
+    assert!(test_error("[1 2 3] V(l)").contains("(l)floor has no inverse"));
+    assert!(test_error("12i V(3C)").contains("Cannot unchunk an array with horizontal form [12]"));
+}
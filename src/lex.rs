@@ -2,7 +2,7 @@ use std::{fmt, ops::Deref, path::PathBuf};
 
 use ecow::EcoString;
 
-use crate::{LocatedError, PrimKind, Primitive};
+use crate::{LocatedError, PrimKind, Primitive, Signature};
 
 pub fn lex(
     src: InputSrc,
@@ -32,6 +32,8 @@ fn lex_impl(
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     Primitive(Primitive),
+    Ident(EcoString),
+    Str(EcoString),
     Number,
     OpenParen,
     CloseParen,
@@ -41,6 +43,7 @@ pub enum Token {
     CloseCurly,
     Equals,
     Bar,
+    Sig(Signature),
     Newline,
 }
 
@@ -64,6 +67,11 @@ impl<'a> Lexer<'a> {
     fn next_char_exact(&mut self, c: char) -> bool {
         self.next_char_if(|c_| c_ == c).is_some()
     }
+    fn natural(&mut self) -> Option<usize> {
+        let start = self.loc.byte;
+        while self.next_char_if(|c| c.is_ascii_digit()).is_some() {}
+        self.text[start..self.loc.byte].parse().ok()
+    }
     fn end(&mut self, start: Loc, token: impl Into<Token>) {
         let end = self.loc;
         let span = Span {
@@ -94,6 +102,22 @@ impl<'a> Lexer<'a> {
                 '{' => self.end(start, Token::OpenCurly),
                 '}' => self.end(start, Token::CloseCurly),
                 '=' => self.end(start, Token::Equals),
+                // Signatures only follow the `=` of a binding,
+                // so the bars between switch branches are never signatures
+                '|' if (self.tokens.last()).is_some_and(|t| t.value == Token::Equals) => {
+                    let args = self.natural();
+                    let outputs = if self.next_char_exact('.') {
+                        self.natural()
+                    } else {
+                        Some(1)
+                    };
+                    match args.zip(outputs) {
+                        Some((args, outputs)) => {
+                            self.end(start, Token::Sig(Signature::new(args, outputs)))
+                        }
+                        None => return Err(self.span(start).sp(LexError::InvalidSignature)),
+                    }
+                }
                 '|' => self.end(start, Token::Bar),
                 '\n' => self.end(start, Token::Newline),
                 ' ' | '\t' | '\r' => {}
@@ -118,6 +142,47 @@ impl<'a> Lexer<'a> {
                     self.end(start, Token::Number);
                 }
                 '#' => while self.next_char_if(|c| c != '\n').is_some() {},
+                // Strings start with `@` so that `"` is free for flip
+                '@' if self.next_char_exact('"') => {
+                    let mut s = EcoString::new();
+                    loop {
+                        let c = match self.next_char_if(|_| true) {
+                            Some('"') => break,
+                            Some('\\') => self.next_char_if(|_| true).map(|c| match c {
+                                'n' => '\n',
+                                c => c,
+                            }),
+                            c => c,
+                        };
+                        match c {
+                            Some(c) => s.push(c),
+                            None => return Err(self.span(start).sp(LexError::UnterminatedString)),
+                        }
+                    }
+                    self.end(start, Token::Str(s));
+                }
+                '@' => {
+                    let name_start = self.loc.byte;
+                    while self.next_char_if(|c| c.is_ascii_alphabetic()).is_some() {}
+                    // Names of imported bindings are prefixed with `name.`
+                    while self.text[self.loc.byte..].starts_with('.')
+                        && (self.text[self.loc.byte + 1..].chars().next())
+                            .is_some_and(|c| c.is_ascii_alphabetic())
+                    {
+                        self.next_char_exact('.');
+                        while self.next_char_if(|c| c.is_ascii_alphabetic()).is_some() {}
+                    }
+                    let name = &self.text[name_start..self.loc.byte];
+                    if name.is_empty() {
+                        return Err(self.span(start).sp(LexError::ExpectedName));
+                    } else if let Some(prim) =
+                        Primitive::all().find(|p| p.name().replace(' ', "") == name)
+                    {
+                        self.end(start, Token::Primitive(prim));
+                    } else {
+                        self.end(start, Token::Ident(name.into()));
+                    }
+                }
                 c => {
                     if let Some(prim) = Primitive::from_glyph(c) {
                         self.end(start, Token::Primitive(prim));
@@ -134,12 +199,18 @@ impl<'a> Lexer<'a> {
 #[derive(Debug, Clone)]
 pub enum LexError {
     InvalidChar(char),
+    ExpectedName,
+    UnterminatedString,
+    InvalidSignature,
 }
 
 impl fmt::Display for LexError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LexError::InvalidChar(c) => write!(f, "Invalid chararacter: {c:?}"),
+            LexError::ExpectedName => write!(f, "Expected a name after @"),
+            LexError::UnterminatedString => write!(f, "Unterminated string"),
+            LexError::InvalidSignature => write!(f, "Invalid signature"),
         }
     }
 }
@@ -332,3 +403,19 @@ impl Deref for Inputs {
         &self.inputs
     }
 }
+
+#[cfg(test)]
+#[test]
+fn strings_and_flip() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("@\"Hi!\""), test_run("[72 105 33]"));
+    assert_eq!(
+        test_run("@\"a\\\"b\\\\c\\nd\""),
+        test_run("[97 34 98 92 99 10 100]")
+    );
+    assert_eq!(test_run("@\"\" n"), test_run("0"));
+    // A bare quote is flip
+    assert_eq!(test_run("1 2 \"-"), test_run("2 1 -"));
+    assert!(test_error("@\"abc").contains("Unterminated string"));
+    assert!(test_error("@\"abc\\").contains("Unterminated string"));
+}
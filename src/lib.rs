@@ -6,7 +6,9 @@ mod dyadic;
 mod error;
 mod form;
 mod gridfmt;
+mod invert;
 mod lex;
+mod loops;
 mod monadic;
 mod parse;
 mod pervade;
@@ -0,0 +1,821 @@
+use ecow::{EcoString, EcoVec};
+
+use crate::{
+    cowslice::CowSlice, pervade::pervade, reduce::reduce, Array, DyMod, Form, FormDims, Mod, Ori,
+    SigNode, Ufel, UfelResult,
+};
+
+pub fn stencil(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    if f.sig != (1, 1) {
+        return Err(rt.error(format!(
+            "{:?}'s function must have 1 argument and 1 output, \
+            but its signature is {:?}",
+            Mod::Stencil,
+            f.sig
+        )));
+    }
+    let spec = rt.pop(1)?;
+    let arr = rt.pop(2)?;
+    let ori = rt.ori();
+    let (mut windows, k) = arr.windows_impl(spec, rt)?;
+    let (vert, hori) = (windows.form.vert_rank(), windows.form.hori_rank());
+    let dims = windows.form.dims();
+    let (count_form, window_form) = match ori {
+        Ori::Hori => (
+            Form::new(k, hori, dims[..k * hori].into()),
+            Form::new(vert - k, hori, dims[k * hori..].into()),
+        ),
+        Ori::Vert => {
+            // Move the count columns to the front so that each window is contiguous
+            let mut count_dims = FormDims::with_capacity(vert * k);
+            let mut window_dims = FormDims::with_capacity(vert * (hori - k));
+            let mut dests = Vec::with_capacity(dims.len());
+            for (pos, &dim) in dims.iter().enumerate() {
+                let (r, c) = (pos / hori, pos % hori);
+                if c < k {
+                    count_dims.push(dim);
+                    dests.push(r * k + c);
+                } else {
+                    window_dims.push(dim);
+                    dests.push(vert * k + r * (hori - k) + c - k);
+                }
+            }
+            windows = windows.move_axes(&dests, rt)?;
+            (
+                Form::new(vert, k, count_dims),
+                Form::new(vert, hori - k, window_dims),
+            )
+        }
+    };
+    let window_len = window_form.elems();
+    let mut results = Vec::with_capacity(count_form.elems());
+    for i in 0..count_form.elems() {
+        let window = windows.data.slice(i * window_len..(i + 1) * window_len);
+        rt.push(Array::new(window_form.clone(), window));
+        rt.exec(f.node.clone())?;
+        results.push(rt.pop(1)?);
+    }
+    let res = combine(count_form, results, ori, Mod::Stencil, rt)?;
+    rt.push(res);
+    Ok(())
+}
+
+/// Combine the results of calling a function on each cell of an outer form
+///
+/// The outer axes lead in the given orientation. Horizontally, each form row
+/// of the results continues the matching form row of the outer form.
+/// Vertically, the form rows of the results follow those of the outer form.
+/// Any form positions left over are unit axes.
+fn combine(outer: Form, results: Vec<Array>, ori: Ori, prim: Mod, rt: &Ufel) -> UfelResult<Array> {
+    let Some(first) = results.first() else {
+        return Ok(Array::new(outer, EcoVec::new().into()));
+    };
+    let res_form = first.form.clone();
+    let mut data = EcoVec::with_capacity(results.len() * res_form.elems());
+    for res in results {
+        if res.form != res_form {
+            return Err(rt.error(format!(
+                "{prim:?}'s function results must all have the same form, \
+                but two have forms {:?} and {:?}",
+                res_form, res.form
+            )));
+        }
+        data.extend_from_slice(&res.data);
+    }
+    if res_form.is_scalar() {
+        return Ok(Array::new(outer, data.into()));
+    }
+    let (outer_vert, outer_hori) = (outer.vert_rank(), outer.hori_rank());
+    let (res_vert, res_hori) = (res_form.vert_rank(), res_form.hori_rank());
+    let (vert, hori, res_start) = match ori {
+        Ori::Hori => (
+            outer_vert.max(res_vert),
+            outer_hori + res_hori,
+            (0, outer_hori),
+        ),
+        Ori::Vert => (
+            outer_vert + res_vert,
+            outer_hori.max(res_hori),
+            (outer_vert, 0),
+        ),
+    };
+    // The data is laid out with the outer axes before the result axes
+    let mut dims: FormDims = (outer.dims().iter().chain(res_form.dims()))
+        .copied()
+        .collect();
+    dims.resize(vert * hori, 1);
+    let outer_dests = (0..outer.dims_rank()).map(|k| (k / outer_hori) * hori + k % outer_hori);
+    let res_dests = (0..res_form.dims_rank())
+        .map(|k| (res_start.0 + k / res_hori) * hori + res_start.1 + k % res_hori);
+    let dests: Vec<usize> = outer_dests.chain(res_dests).collect();
+    Array::new(Form::new(vert, hori, dims), data.into()).move_axes(&dests, rt)
+}
+
+pub fn rows(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let ori = rt.ori();
+    let args = rt.take_n(f.sig.args)?;
+    let mut row_count = None;
+    for arg in &args {
+        let n = arg.form.row_count(ori);
+        if arg.form.is_scalar() || n == 1 {
+            continue;
+        }
+        match row_count {
+            Some(count) if count != n => {
+                return Err(rt.error(format!(
+                    "Cannot {:?} arrays with {count} and {n} {} rows",
+                    Mod::Rows,
+                    ori.str()
+                )))
+            }
+            _ => row_count = Some(n),
+        }
+    }
+    // Scalars have no rows, so the function is simply called on them
+    if args.iter().all(|arg| arg.form.is_scalar()) {
+        for arg in args {
+            rt.push(arg);
+        }
+        return rt.exec(f.node);
+    }
+    let row_count = row_count.unwrap_or(1);
+    if row_count == 0 {
+        return empty_rows(f, args, ori, rt);
+    }
+    // Scalars and single rows are repeated for every row
+    let mut args: Vec<Result<Array, Box<dyn Iterator<Item = Array>>>> = (args.into_iter())
+        .map(|arg| {
+            if arg.form.is_scalar() {
+                Ok(arg)
+            } else if arg.form.row_count(ori) == 1 && row_count != 1 {
+                Ok(arg.into_rows(ori).next().unwrap())
+            } else {
+                Err(arg.into_rows(ori))
+            }
+        })
+        .collect();
+    let mut outputs = vec![Vec::with_capacity(row_count); f.sig.outputs];
+    for _ in 0..row_count {
+        for arg in &mut args {
+            match arg {
+                Ok(arg) => rt.push(arg.clone()),
+                Err(rows) => rt.push(rows.next().unwrap()),
+            }
+        }
+        rt.exec(f.node.clone())?;
+        for output in outputs.iter_mut().rev() {
+            output.push(rt.pop(1)?);
+        }
+    }
+    for output in outputs {
+        let res = Array::from_row_arrays(output, rt)?;
+        rt.push(res);
+    }
+    Ok(())
+}
+
+/// Call a rows function when there are no rows
+///
+/// The function is called once on rows of zeros to find the forms of its output rows
+fn empty_rows(f: SigNode, args: Vec<Array>, ori: Ori, rt: &mut Ufel) -> UfelResult {
+    for arg in args {
+        if arg.form.is_scalar() {
+            rt.push(arg);
+        } else {
+            let row_form = arg.form.row(ori);
+            let data = CowSlice::from_elem(0.0, row_form.elems());
+            rt.push(Array::new(row_form, data));
+        }
+    }
+    rt.exec(f.node)?;
+    for output in rt.take_n(f.sig.outputs)? {
+        let mut form = output.form;
+        form.fix(ori);
+        form[0][0] = 0;
+        rt.push(Array::new(form, CowSlice::new()));
+    }
+    Ok(())
+}
+
+pub fn each(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let args = rt.take_n(f.sig.args)?;
+    let mut form = None;
+    for arg in &args {
+        if arg.form.is_scalar() {
+            continue;
+        }
+        match &form {
+            Some(form) if *form != arg.form => {
+                return Err(rt.error(format!(
+                    "Cannot {:?} arrays with forms {:?} and {:?}",
+                    Mod::Each,
+                    form,
+                    arg.form
+                )))
+            }
+            _ => form = Some(arg.form.clone()),
+        }
+    }
+    let form = form.unwrap_or_else(Form::scalar);
+    let mut outputs = vec![Vec::with_capacity(form.elems()); f.sig.outputs];
+    for i in 0..form.elems() {
+        for arg in &args {
+            // Scalars are repeated for every element
+            let elem = if arg.form.is_scalar() {
+                arg.data[0]
+            } else {
+                arg.data[i]
+            };
+            rt.push(elem);
+        }
+        rt.exec(f.node.clone())?;
+        for output in outputs.iter_mut().rev() {
+            output.push(rt.pop(1)?);
+        }
+    }
+    for output in outputs {
+        let res = combine(form.clone(), output, rt.ori(), Mod::Each, rt)?;
+        rt.push(res);
+    }
+    Ok(())
+}
+
+pub fn table(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    if f.sig != (2, 1) {
+        return Err(rt.error(format!(
+            "{:?}'s function must have 2 arguments and 1 output, \
+            but its signature is {:?}",
+            Mod::Table,
+            f.sig
+        )));
+    }
+    let ori = rt.ori();
+    let ys = rt.pop(1)?;
+    let xs = rt.pop(2)?;
+    let (m, n) = (xs.form.row_count(ori), ys.form.row_count(ori));
+    // The row axes of the result keep the labels of the leading axes
+    let labels = [&xs, &ys].map(|arr| arr.form.label(0).map(EcoString::from));
+    let label = |mut form: Form, n_axis: usize| {
+        form.set_label(0, labels[0].clone());
+        form.set_label(n_axis, labels[1].clone());
+        form
+    };
+    let pervasive = (f.node.as_flipped_dy())
+        .and_then(|(prim, flipped)| prim.pervasive_fn().map(|f| (f, flipped)));
+    // Fast path for pervasive functions on lists
+    if let Some((op, flipped)) = pervasive {
+        if xs.form.row(ori).is_scalar() && ys.form.row(ori).is_scalar() {
+            let (xs, ys) = (xs.row_data(ori), ys.row_data(ori));
+            let mut data = EcoVec::with_capacity(m * n);
+            for &x in &xs {
+                for &y in &ys {
+                    data.push(if flipped { op(x, y) } else { op(y, x) });
+                }
+            }
+            rt.push(Array::new(label(Form::from([[m], [n]]), 1), data.into()));
+            return Ok(());
+        }
+    }
+    let ys: Vec<Array> = ys.into_rows(ori).collect();
+    let mut results = Vec::with_capacity(m * n);
+    for x in xs.into_rows(ori) {
+        for y in &ys {
+            let res = match pervasive {
+                Some((op, false)) => pervade(y.clone(), x.clone(), 0, 0, op, rt)?,
+                Some((op, true)) => pervade(x.clone(), y.clone(), 0, 0, op, rt)?,
+                None => {
+                    rt.push(x.clone());
+                    rt.push(y.clone());
+                    rt.exec(f.node.clone())?;
+                    rt.pop(1)?
+                }
+            };
+            results.push(res);
+        }
+    }
+    // The rows of the first array run horizontally and those of the second vertically
+    let combined = combine(Form::from([m * n]), results, Ori::Hori, Mod::Table, rt)?;
+    let Some(combined_dims) = combined.form.as_normal() else {
+        return Err(rt.error(format!(
+            "{:?}'s function results must be scalars or normal, but one has form {:?}",
+            Mod::Table,
+            combined.form.row(Ori::Hori)
+        )));
+    };
+    let res_dims = &combined_dims[1..];
+    let res_len: usize = res_dims.iter().product();
+    let mut dims = FormDims::with_capacity(2 * (res_dims.len() + 1));
+    dims.push(m);
+    dims.extend_from_slice(res_dims);
+    dims.push(n);
+    dims.extend(res_dims.iter().map(|_| 1));
+    let mut data = EcoVec::with_capacity(combined.data.len());
+    for i in 0..m {
+        for r in 0..res_len {
+            for j in 0..n {
+                data.push(combined.data[(i * n + j) * res_len + r]);
+            }
+        }
+    }
+    let form = label(Form::new(2, res_dims.len() + 1, dims), res_dims.len() + 1);
+    rt.push(Array::new(form, data.into()));
+    Ok(())
+}
+
+pub fn group(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let keys = rt.pop(1)?;
+    let arr = rt.pop(2)?;
+    let keys = group_keys(keys, &arr, Mod::Group, rt)?;
+    // Groups past the last row would all be empty
+    if let Some(&key) = keys.iter().find(|&&key| key >= keys.len() as isize) {
+        return Err(rt.error(format!(
+            "{:?} keys must be less than the number of rows {}, but one is {key}",
+            Mod::Group,
+            keys.len()
+        )));
+    }
+    let group_count = keys
+        .iter()
+        .max()
+        .map_or(0, |&max| (max + 1).max(0) as usize);
+    let mut groups = vec![Vec::new(); group_count];
+    for (i, &key) in keys.iter().enumerate() {
+        if key >= 0 {
+            groups[key as usize].push(i);
+        }
+    }
+    collect_groups(f, arr, groups, Mod::Group, rt)
+}
+
+pub fn partition(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let keys = rt.pop(1)?;
+    let arr = rt.pop(2)?;
+    let keys = group_keys(keys, &arr, Mod::Partition, rt)?;
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut prev = 0;
+    for (i, &key) in keys.iter().enumerate() {
+        if key > 0 {
+            if key != prev {
+                groups.push(Vec::new());
+            }
+            groups.last_mut().unwrap().push(i);
+        }
+        prev = key;
+    }
+    collect_groups(f, arr, groups, Mod::Partition, rt)
+}
+
+fn group_keys(keys: Array, arr: &Array, prim: Mod, rt: &Ufel) -> UfelResult<Vec<isize>> {
+    let ori = rt.ori();
+    if !keys.form.is_list() {
+        return Err(rt.error(format!(
+            "{prim:?} keys must be a list, but their form is {:?}",
+            keys.form
+        )));
+    }
+    let row_count = arr.form.row_count(ori);
+    if keys.data.len() != row_count {
+        return Err(rt.error(format!(
+            "Cannot {prim:?} array with {row_count} {} rows using {} keys",
+            ori.str(),
+            keys.data.len()
+        )));
+    }
+    let mut res = Vec::with_capacity(keys.data.len());
+    for &key in &keys.data {
+        if key.fract() != 0.0 {
+            return Err(rt.error(format!(
+                "{prim:?} keys must be all integers, but one element is {key}"
+            )));
+        }
+        res.push(key as isize);
+    }
+    Ok(res)
+}
+
+fn collect_groups(
+    f: SigNode,
+    arr: Array,
+    groups: Vec<Vec<usize>>,
+    prim: Mod,
+    rt: &mut Ufel,
+) -> UfelResult {
+    if f.sig != (1, 1) && f.sig != (2, 1) {
+        return Err(rt.error(format!(
+            "{prim:?}'s function must have 1 or 2 arguments and 1 output, \
+            but its signature is {:?}",
+            f.sig
+        )));
+    }
+    let ori = rt.ori();
+    let mut empty_form = arr.form.row(ori);
+    empty_form.fix(ori);
+    empty_form[0][0] = 0;
+    let rows: Vec<Array> = arr.into_rows(ori).collect();
+    let mut results = Vec::with_capacity(groups.len());
+    for group in groups {
+        let group = if group.is_empty() {
+            Array::new(empty_form.clone(), EcoVec::new().into())
+        } else {
+            Array::from_row_arrays_ori(group.into_iter().map(|i| rows[i].clone()), ori, rt)?
+        };
+        rt.push(group);
+        // Dyadic functions reduce each group
+        if f.sig == (2, 1) {
+            reduce(f.clone(), rt)?;
+        } else {
+            rt.exec(f.node.clone())?;
+        }
+        results.push(rt.pop(1)?);
+    }
+    let res = Array::from_row_arrays(results, rt)?;
+    rt.push(res);
+    Ok(())
+}
+
+pub fn repeat(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let n = rt.pop(1)?;
+    let n = match n.form.is_scalar().then(|| n.data[0]) {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+        _ => {
+            return Err(rt.error(format!(
+                "{:?} count must be a non-negative integer scalar, but it is {n}",
+                Mod::Repeat
+            )))
+        }
+    };
+    if n > rt.iteration_limit() {
+        return Err(rt.error(format!(
+            "{:?} count {n} exceeds the iteration limit of {}",
+            Mod::Repeat,
+            rt.iteration_limit()
+        )));
+    }
+    for _ in 0..n {
+        rt.exec(f.node.clone())?;
+    }
+    Ok(())
+}
+
+pub fn do_(f: SigNode, g: SigNode, rt: &mut Ufel) -> UfelResult {
+    if g.sig.outputs != 1 {
+        return Err(rt.error(format!(
+            "{:?}'s condition function must have 1 output, \
+            but its signature is {:?}",
+            DyMod::Do,
+            g.sig
+        )));
+    }
+    let cond = |rt: &mut Ufel| -> UfelResult<bool> {
+        let args = rt.copy_n(g.sig.args)?;
+        for arg in args {
+            rt.push(arg);
+        }
+        rt.exec(g.node.clone())?;
+        let cond = rt.pop(1)?;
+        match cond.form.is_scalar().then(|| cond.data[0]) {
+            Some(0.0) => Ok(false),
+            Some(1.0) => Ok(true),
+            _ => Err(rt.error(format!(
+                "{:?}'s condition must be a boolean scalar, but it is {cond}",
+                DyMod::Do
+            ))),
+        }
+    };
+    // The body may run as many times as the limit, after which the condition must be false
+    for _ in 0..rt.iteration_limit() {
+        if !cond(rt)? {
+            return Ok(());
+        }
+        rt.exec(f.node.clone())?;
+    }
+    if !cond(rt)? {
+        return Ok(());
+    }
+    Err(rt.error(format!(
+        "{:?} exceeded the iteration limit of {}",
+        DyMod::Do,
+        rt.iteration_limit()
+    )))
+}
+
+pub fn fixed_point(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    let mut prev = rt.pop(1)?;
+    rt.push(prev.clone());
+    for _ in 0..rt.iteration_limit() {
+        rt.exec(f.node.clone())?;
+        let curr = rt.pop(1)?;
+        rt.push(curr.clone());
+        if curr == prev {
+            return Ok(());
+        }
+        prev = curr;
+    }
+    Err(rt.error(format!(
+        "{:?} did not converge within the iteration limit of {}",
+        Mod::FixedPoint,
+        rt.iteration_limit()
+    )))
+}
+
+/// Call a function with the axis at a form position moved to the front
+///
+/// Only the top argument has its axes moved,
+/// and only the last output has them moved back
+pub fn axis(f: SigNode, rt: &mut Ufel) -> UfelResult {
+    if f.sig.args == 0 {
+        return Err(rt.error(format!(
+            "{:?}'s function must take at least 1 argument, \
+            but its signature is {:?}",
+            Mod::Axis,
+            f.sig
+        )));
+    }
+    let pos = rt.pop(1)?;
+    let arr = rt.pop(2)?;
+    let ori = rt.ori();
+    let (row, col) = match (pos.form.as_normal(), pos.data.as_slice()) {
+        (Some([]), &[n]) | (Some([1]), &[n]) if n >= 0.0 && n.fract() == 0.0 => match ori {
+            Ori::Hori => (0, n as usize),
+            Ori::Vert => (n as usize, 0),
+        },
+        (Some([2]), &[r, c]) if [r, c].iter().all(|&d| d >= 0.0 && d.fract() == 0.0) => {
+            (r as usize, c as usize)
+        }
+        _ => {
+            return Err(rt.error(format!(
+                "{:?} position must be a natural or a pair of naturals, but it is {pos}",
+                Mod::Axis
+            )))
+        }
+    };
+    let (vert, hori) = (arr.form.vert_rank(), arr.form.hori_rank());
+    if row >= vert || col >= hori {
+        return Err(rt.error(format!(
+            "{:?} position ({row}, {col}) is out of bounds of form {:?}",
+            Mod::Axis,
+            arr.form
+        )));
+    }
+    // Rotate the chosen axis to the front of the order that rows are indexed by,
+    // keeping the other axes in order
+    let rank = vert * hori;
+    let order = axis_order(vert, hori, ori);
+    let pos = order.iter().position(|&i| i == row * hori + col).unwrap();
+    let seq: Vec<usize> = [pos]
+        .into_iter()
+        .chain((0..rank).filter(|&p| p != pos))
+        .collect();
+    let mut dests = vec![0; rank];
+    for (p, &q) in seq.iter().enumerate() {
+        dests[order[q]] = order[p];
+    }
+    rt.push(arr.move_axes(&dests, rt)?);
+    rt.exec(f.node)?;
+    if f.sig.outputs == 0 {
+        return Ok(());
+    }
+    let res = rt.pop(1)?;
+    // If the function removed leading axes, the remaining ones are put back
+    // in their original order. Any other change to the rank is left as is.
+    let res_rank = res.form.dims_rank();
+    let res = if res_rank > 0 && res_rank <= rank {
+        let remaining = &seq[rank - res_rank..];
+        let mut sorted = remaining.to_vec();
+        sorted.sort_unstable();
+        let order = axis_order(res.form.vert_rank(), res.form.hori_rank(), ori);
+        let mut dests = vec![0; res_rank];
+        for (p, q) in remaining.iter().enumerate() {
+            dests[order[p]] = order[sorted.binary_search(q).unwrap()];
+        }
+        res.move_axes(&dests, rt)?
+    } else {
+        res
+    };
+    rt.push(res);
+    Ok(())
+}
+
+/// Get the indices into [`Form::dims`] of a form's axes in the order that rows are indexed by
+///
+/// Horizontal rows are indexed by the first form column, so the axes go column by column.
+/// Vertical rows are indexed by the first form row, so the axes go row by row.
+fn axis_order(vert: usize, hori: usize, ori: Ori) -> Vec<usize> {
+    match ori {
+        Ori::Hori => (0..vert * hori)
+            .map(|i| (i % vert) * hori + i / vert)
+            .collect(),
+        Ori::Vert => (0..vert * hori).collect(),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn windows_and_stencil() {
+    use crate::{test_error, test_run};
+    assert_eq!(
+        test_run("[1 2 3 4] 2W"),
+        test_run("{[1 2 2 3 3 4] [[3] [2]]}")
+    );
+    assert_eq!(test_run("12i [3 4]H [2 2] W m"), test_run("[[2 3] [2 2]]"));
+    assert_eq!(test_run("[1 2 3 4] 2 \\(r+)"), test_run("[3 5 7]"));
+    assert_eq!(
+        test_run("12i [3 4]H [2 2] \\(r+ r+)"),
+        test_run("[[10 14 18] [26 30 34]]")
+    );
+    assert_eq!(
+        test_run("[1 2 3] 2 \\(:J)"),
+        test_run("[[1 2 1 2] [2 3 2 3]]")
+    );
+    // Vertical stencils put the result axes below the window counts
+    assert_eq!(
+        test_run("12i 4C 2 ~\\(r+)"),
+        test_run("{[4 6 8 10 12 14 16 18] [[2] [4] [1]]}")
+    );
+    assert_eq!(test_run("12i 4C 2 ~\\(.) m"), test_run("[[2] [4] [2] [1]]"));
+    assert!(test_error("[1 2 3] 5W").contains("Window size 5 is too large for axis 0 size 3"));
+    assert!(test_error("[1 2 3] 2 \\(+)").contains("must have 1 argument and 1 output"));
+}
+
+#[cfg(test)]
+#[test]
+fn group_and_partition() {
+    use crate::{test_error, test_run};
+    assert_eq!(
+        test_run("[1 2 3 4 5] [0 1 0 1 2] @group+"),
+        test_run("[4 6 5]")
+    );
+    assert_eq!(
+        test_run("[1 2 3 4 5] [0 `1 0 1 1] @group(r+)"),
+        test_run("[4 9]")
+    );
+    assert_eq!(
+        test_run("[[1 2] [3 4] [5 6]] [0 1 0] @group(r+)"),
+        test_run("[[6 8] [3 4]]")
+    );
+    assert_eq!(
+        test_run("[1 2 3 4 5] [1 1 0 2 2] @partition(r+)"),
+        test_run("[3 9]")
+    );
+    assert_eq!(
+        test_run("[1 2 3 4] [1 1 1 1] @partition(r+)"),
+        test_run("[10]")
+    );
+    assert!(test_error("[1 2 3] [0 1e15 0] @group+").contains(
+        "@group keys must be less than the number of rows 3, but one is 1000000000000000"
+    ));
+    assert!(test_error("[1 2 3] [0 1] @group(r+)")
+        .contains("Cannot @group array with 3 horizontal rows using 2 keys"));
+    assert!(test_error("[1 2 3] [0 0.5 1] @partition(r+)")
+        .contains("@partition keys must be all integers"));
+}
+
+#[cfg(test)]
+#[test]
+fn rows_and_each() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3] y(2*)"), test_run("[2 4 6]"));
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [10 20] y+"),
+        test_run("[[11 12] [23 24]]")
+    );
+    assert_eq!(
+        test_run("[[1 2] [3 4]] 10 y+"),
+        test_run("[[11 12] [13 14]]")
+    );
+    // Empty arrays keep the forms of the function's output rows
+    assert_eq!(test_run("0i [0 3]H y(:J) m"), test_run("[[0 6]]"));
+    assert_eq!(test_run("0i [0 3]H y(r+) m"), test_run("[[0]]"));
+    assert_eq!(test_run("{[] [[0] [3]]} ~y(:J) m"), test_run("[[0] [6]]"));
+    assert_eq!(test_run("[1 2 3] [4 5 6] $+"), test_run("[5 7 9]"));
+    assert_eq!(test_run("[1 2 3] $(:J) m"), test_run("[[3 2]]"));
+    // Only scalars lose their form
+    assert_eq!(test_run("5 y(1+)"), test_run("6"));
+    assert_eq!(test_run("[5] y(1+)"), test_run("[6]"));
+    assert_eq!(test_run("[5] $(1+)"), test_run("[6]"));
+    assert_eq!(test_run("[[5]] 5 $+"), test_run("[[10]]"));
+    assert_eq!(test_run("5 5 $+"), test_run("10"));
+    assert!(test_error("[1 2] [3 4 5] y+")
+        .contains("Cannot (y)rows arrays with 2 and 3 horizontal rows"));
+    assert!(test_error("[1 2] [3 4 5] $+").contains("Cannot ($)each arrays with forms"));
+    assert!(test_error("[[5]] [5] $+").contains("Cannot ($)each arrays with forms [1×1] and [1]"));
+    assert!(test_error("[1 2] $(i)").contains(
+        "($)each's function results must all have the same form, but two have forms [1] and [2]"
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn table_() {
+    use crate::{test_error, test_run};
+    // The rows of the first array run horizontally and those of the second vertically
+    assert_eq!(
+        test_run("[1 2 3] [10 20] O+"),
+        test_run("{[11 21 12 22 13 23] [[3] [2]]}")
+    );
+    assert_eq!(
+        test_run("[1 2 3] [10 20] O-"),
+        test_run("[1 2 3] [10 20] O(-)")
+    );
+    assert_eq!(test_run("[1 2] [3 4 5] O(K) m"), test_run("[[2 2] [3 1]]"));
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [10 20] O+ m"),
+        test_run("[[2 2] [2 1]]")
+    );
+    assert!(test_error("[1 2] [3 4] O(.;)")
+        .contains("(O)table's function must have 2 arguments and 1 output"));
+}
+
+#[cfg(test)]
+#[test]
+fn repeat_do_and_fixed_point() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("1 5<(2*)"), test_run("32"));
+    assert_eq!(test_run("1 0<(2*)"), test_run("1"));
+    assert_eq!(test_run("1 >(2*)(100L)"), test_run("128"));
+    assert_eq!(test_run("1 >(2*)(0)"), test_run("1"));
+    assert_eq!(test_run("64 Y(2/l)"), test_run("0"));
+    assert!(test_error("1 `1<(2*)")
+        .contains("(<)repeat count must be a non-negative integer scalar, but it is -1"));
+    assert!(test_error("1 >(2*)(2)")
+        .contains("(>)do's condition must be a boolean scalar, but it is 2"));
+    assert!(test_error("1 >(2*)(:1L)").contains("(>)do's condition function must have 1 output"));
+    // The limit bounds the number of times the body runs
+    let run = |code: &str| {
+        let mut rt = Ufel::new().with_iteration_limit(3);
+        rt.run_str(code).map(|_| rt.take_stack())
+    };
+    assert_eq!(run("1 3<(2*)").unwrap(), test_run("8"));
+    assert_eq!(run("1 >(2*)(8L)").unwrap(), test_run("8"));
+    assert!((run("1 4<(2*)").unwrap_err().to_string())
+        .contains("(<)repeat count 4 exceeds the iteration limit of 3"));
+    assert!((run("1 >(2*)(16L)").unwrap_err().to_string())
+        .contains("(>)do exceeded the iteration limit of 3"));
+    assert!((run("64 Y(2/l)").unwrap_err().to_string())
+        .contains("(Y)fixed point did not converge within the iteration limit of 3"));
+}
+
+#[cfg(test)]
+#[test]
+fn axis_() {
+    use crate::{test_error, test_run};
+    let normal = "24i [2 3 4] H ";
+    assert_eq!(
+        test_run(&format!("{normal}2 @axis(r+)")),
+        test_run("[[6 22 38] [54 70 86]]")
+    );
+    assert_eq!(
+        test_run(&format!("{normal}1 @axis(r+)")),
+        test_run("[[12 15 18 21] [48 51 54 57]]")
+    );
+    // The other axes keep their order
+    assert_eq!(
+        test_run(&format!("{normal}2 @axis(m)")),
+        test_run("[[4 2 3]]")
+    );
+    assert_eq!(test_run(&format!("{normal}2 @axis(.)")), test_run(normal));
+    assert_eq!(
+        test_run(&format!("{normal}2 @axis(z)")),
+        test_run(&format!("{normal}y(y(z))"))
+    );
+    assert_eq!(
+        test_run(&format!("{normal}1 @axis(1T) m")),
+        test_run("[[2 1 4]]")
+    );
+    // Horizontally, axes lead column by column, and vertically, row by row
+    let matrix = "{24i [[2 3] [4 1]]} ";
+    assert_eq!(
+        test_run(&format!("{matrix}[1 1] @axis(m)")),
+        test_run("[[1 4] [2 3]]")
+    );
+    assert_eq!(
+        test_run(&format!("{matrix}[1 0] @axis(m)")),
+        test_run("[[4 3] [2 1]]")
+    );
+    assert_eq!(
+        test_run(&format!("{matrix}[0 1] ~@axis(m)")),
+        test_run("[[3 2] [4 1]]")
+    );
+    assert_eq!(
+        test_run(&format!("{matrix}[1 0] ~@axis(m)")),
+        test_run("[[4 2] [3 1]]")
+    );
+    for pos in ["[0 1]", "[1 0]", "[1 1]"] {
+        assert_eq!(
+            test_run(&format!("{matrix}{pos} @axis(.)")),
+            test_run(matrix)
+        );
+        assert_eq!(
+            test_run(&format!("{matrix}{pos} ~@axis(.)")),
+            test_run(matrix)
+        );
+    }
+    assert_eq!(
+        test_run(&format!("{matrix}[1 0] @axis(r+) m")),
+        test_run("[[3] [1]]")
+    );
+    assert!(test_error(&format!("{normal}5 @axis(r+)"))
+        .contains("@axis position (0, 5) is out of bounds of form [2×3×4]"));
+    assert!(test_error(&format!("{normal}`1 @axis(r+)"))
+        .contains("@axis position must be a natural or a pair of naturals, but it is -1"));
+}
@@ -90,7 +90,9 @@ fn run(src: InputSrc, text: &str) {
         println!("{}", val.show());
     }
     if let Err(e) = res {
-        eprintln!("{e}");
+        for e in e {
+            eprintln!("{e}");
+        }
     }
 }
 
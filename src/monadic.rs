@@ -1,4 +1,8 @@
-use std::ptr;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    ptr,
+};
 
 use ecow::{eco_vec, EcoVec};
 
@@ -50,6 +54,43 @@ impl Array {
             }
         })
     }
+    pub fn where_(self, rt: &Ufel) -> UfelResult<Array> {
+        let ori = rt.ori();
+        if self.form.is_scalar() {
+            let n = (self.data[0] != 0.0) as usize;
+            return Ok(Array::new(n.into(), eco_vec![0.0; n].into()));
+        }
+        // Each index picks a row in the current orientation
+        let form = &self.form;
+        let hori = form.hori_rank();
+        let rank = form.rank(ori);
+        let level = |pos: usize| match ori {
+            Ori::Hori => pos % hori,
+            Ori::Vert => pos / hori,
+        };
+        let mut index = Vec::with_capacity(form.dims_rank());
+        let mut indices = Vec::new();
+        for (i, &elem) in self.data.iter().enumerate() {
+            if elem == 0.0 {
+                continue;
+            }
+            flat_to_dims(form.dims(), i, &mut index);
+            let mut levels = vec![0; rank];
+            for (pos, (&dim, &j)) in form.dims().iter().zip(&index).enumerate() {
+                let level = &mut levels[level(pos)];
+                *level = *level * dim + j;
+            }
+            indices.push(levels);
+        }
+        indices.sort_unstable();
+        let count = indices.len();
+        let data: EcoVec<f64> = indices.into_iter().flatten().map(|i| i as f64).collect();
+        Ok(if rank == 1 {
+            data.into()
+        } else {
+            Array::new([count, rank].into(), data.into())
+        })
+    }
 }
 
 impl<T: Element> Array<T> {
@@ -79,6 +120,70 @@ impl<T: Element> Array<T> {
             }
         })
     }
+    pub fn rise(&self, rt: &Ufel) -> UfelResult<Array> {
+        self.grade(Monadic::Rise, false, rt)
+    }
+    pub fn fall(&self, rt: &Ufel) -> UfelResult<Array> {
+        self.grade(Monadic::Fall, true, rt)
+    }
+    pub fn sort(mut self, rt: &Ufel) -> UfelResult<Self> {
+        let ori = rt.ori();
+        let indices = self.grade(Monadic::Sort, false, rt)?;
+        let row_len = self.form.row_len(ori);
+        let data = self.row_data(ori);
+        let mut sorted = EcoVec::with_capacity(data.len());
+        for &i in &indices.data {
+            let i = i as usize;
+            sorted.extend_from_slice(&data[i * row_len..(i + 1) * row_len]);
+        }
+        self.set_row_data(ori, sorted.into());
+        Ok(self)
+    }
+    fn grade(&self, prim: Monadic, descending: bool, rt: &Ufel) -> UfelResult<Array> {
+        if self.form.is_scalar() {
+            return Err(rt.error(format!("Cannot {prim:?} a scalar")));
+        }
+        let row_len = self.form.row_len(rt.ori());
+        let data = self.row_data(rt.ori());
+        let row = |i: usize| &data[i * row_len..(i + 1) * row_len];
+        let mut indices: Vec<usize> = (0..self.form.row_count(rt.ori())).collect();
+        indices.sort_by(|&a, &b| {
+            let (a, b) = if descending { (b, a) } else { (a, b) };
+            (row(a).iter().zip(row(b)))
+                .map(|(a, b)| a.array_cmp(b))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(indices.into_iter().map(|i| i as f64).collect())
+    }
+    pub fn deduplicate(self, rt: &Ufel) -> UfelResult<Self> {
+        if self.form.is_scalar() {
+            return Err(rt.error(format!("Cannot {:?} a scalar", Monadic::Deduplicate)));
+        }
+        let ori = rt.ori();
+        if self.form.row_count(ori) == 0 {
+            return Ok(self);
+        }
+        let mut seen = HashSet::new();
+        let rows = (self.into_rows(ori)).filter(|row| seen.insert(row.clone()));
+        Array::from_row_arrays_ori(rows, ori, rt)
+    }
+    pub fn classify(self, rt: &Ufel) -> UfelResult<Array> {
+        if self.form.is_scalar() {
+            return Err(rt.error(format!("Cannot {:?} a scalar", Monadic::Classify)));
+        }
+        let mut classes = HashMap::new();
+        Ok((self.into_rows(rt.ori()))
+            .map(|row| {
+                let len = classes.len();
+                *classes.entry(row).or_insert(len) as f64
+            })
+            .collect())
+    }
+    pub fn deshape(mut self) -> Self {
+        self.form = self.form.elems().into();
+        self
+    }
     pub fn reverse(mut self, ori: Ori) -> Self {
         let row_count = self.form.row_count(ori);
         let row_len = self.form.row_len(ori);
@@ -111,15 +216,23 @@ impl<T: Element> Array<T> {
         self
     }
     pub fn transpose(self, rt: &Ufel) -> UfelResult<Self> {
+        self.transpose_impl(false, rt)
+    }
+    pub fn untranspose(self, rt: &Ufel) -> UfelResult<Self> {
+        self.transpose_impl(true, rt)
+    }
+    fn transpose_impl(self, inverse: bool, rt: &Ufel) -> UfelResult<Self> {
         let mut axes: Vec<usize> = (0..self.form.dims_rank()).collect();
         let stride = self.form.hori_rank();
-        match rt.ori() {
-            Ori::Hori => {
-                for chunk in axes.chunks_exact_mut(stride) {
-                    chunk.rotate_left(1);
-                }
-            }
-            Ori::Vert => axes.rotate_left(stride),
+        match (rt.ori(), inverse) {
+            (Ori::Hori, false) => axes
+                .chunks_exact_mut(stride)
+                .for_each(|chunk| chunk.rotate_left(1)),
+            (Ori::Hori, true) => axes
+                .chunks_exact_mut(stride)
+                .for_each(|chunk| chunk.rotate_right(1)),
+            (Ori::Vert, false) => axes.rotate_left(stride),
+            (Ori::Vert, true) => axes.rotate_right(stride),
         };
         self.move_axes(&axes, rt)
     }
@@ -130,13 +243,34 @@ impl<T: Element> Array<T> {
             self.form = new_form;
             return Ok(self);
         }
-        let mut dest: Vec<usize> = Vec::with_capacity(self.form.dims_rank());
-        for j in 0..self.form.hori_rank() {
-            for i in 0..self.form.vert_rank() {
-                dest.push(i * self.form.hori_rank() + j);
+        // The axis at form position (i, j) moves to (j, i)
+        let (vert, hori) = (self.form.vert_rank(), self.form.hori_rank());
+        let dest: Vec<usize> = (0..vert * hori)
+            .map(|k| (k % hori) * vert + k / hori)
+            .collect();
+        let mut arr = self.move_axes(&dest, rt)?;
+        arr.form = new_form;
+        Ok(arr)
+    }
+    pub fn deform(self, rt: &Ufel) -> UfelResult<Self> {
+        let (vert, hori) = (self.form.vert_rank(), self.form.hori_rank());
+        match rt.ori() {
+            Ori::Hori if vert > 1 && hori > 1 => {
+                // Horizontal rows are indexed by the first form column,
+                // so the axes are ordered column by column
+                let dests: Vec<usize> = (0..vert * hori)
+                    .map(|k| (k % hori) * vert + k / hori)
+                    .collect();
+                let mut arr = self.move_axes(&dests, rt)?;
+                arr.form.deform(Ori::Hori);
+                Ok(arr)
+            }
+            ori => {
+                let mut arr = self;
+                arr.form.deform(ori);
+                Ok(arr)
             }
         }
-        self.move_axes(&dest, rt)
     }
     pub(crate) fn move_axes(self, indices: &[usize], rt: &Ufel) -> UfelResult<Self> {
         fn derive_orient_data(
@@ -196,12 +330,15 @@ impl<T: Element> Array<T> {
 
         let (indices, new_dims, trailing_dims) = derive_orient_data(indices, self.form.dims(), rt)?;
 
+        let mut new_form = Form::new(self.form.vert_rank(), self.form.hori_rank(), new_dims);
+        new_form.relabel(
+            &self.form,
+            (0..new_form.dims_rank()).map(|i| indices.iter().position(|&j| j == i)),
+        );
+        let new_dims = new_form.dims();
         let new_dims_elems: usize = new_dims.iter().product();
         if new_dims_elems == 0 {
-            return Ok(Array::new(
-                Form::new(self.form.vert_rank(), self.form.hori_rank(), new_dims),
-                CowSlice::new(),
-            ));
+            return Ok(Array::new(new_form, CowSlice::new()));
         } else if trailing_dims == self.form.dims_rank() {
             return Ok(self.clone());
         }
@@ -229,14 +366,11 @@ impl<T: Element> Array<T> {
             row.clone_from_slice(&self.data[j * trailing_row_len..][..trailing_row_len]);
         }
 
-        Ok(Array::new(
-            Form::new(self.form.vert_rank(), self.form.hori_rank(), new_dims),
-            data,
-        ))
+        Ok(Array::new(new_form, data))
     }
 }
 
-fn flat_to_dims(axes: &[usize], flat: usize, index: &mut Vec<usize>) {
+pub(crate) fn flat_to_dims(axes: &[usize], flat: usize, index: &mut Vec<usize>) {
     index.clear();
     let mut flat = flat;
     for &dim in axes.iter().rev() {
@@ -255,3 +389,81 @@ fn dims_to_flat(axes: &[usize], index: &[usize]) -> Option<usize> {
     }
     Some(flat)
 }
+
+#[cfg(test)]
+#[test]
+fn rise_fall_sort() {
+    use crate::test_run;
+    assert_eq!(test_run("[3 1 2] u"), test_run("[1 2 0]"));
+    assert_eq!(test_run("[3 1 2] j"), test_run("[0 2 1]"));
+    assert_eq!(test_run("[3 1 2] s"), test_run("[1 2 3]"));
+    assert_eq!(test_run("[2 1 2 1] u"), test_run("[1 3 0 2]"));
+    assert_eq!(test_run("[3 1 2] :u S"), test_run("[1 2 3]"));
+    assert_eq!(
+        test_run("[[2 1] [1 5] [1 2]] s"),
+        test_run("[[1 2] [1 5] [2 1]]")
+    );
+    assert_eq!(test_run("[0 0/ 1 0 0/ 0] u"), test_run("[3 1 0 2]"));
+    assert_eq!(
+        test_run("{[3 4 5 0 1 2] [[2] [3]]} ~s"),
+        test_run("{[0 1 2 3 4 5] [[2] [3]]}")
+    );
+    assert_eq!(test_run("{[3 4 5 0 1 2] [[2] [3]]} ~u"), test_run("[1 0]"));
+}
+
+#[cfg(test)]
+#[test]
+fn deduplicate_and_classify() {
+    use crate::test_run;
+    assert_eq!(test_run("[3 1 3 2 1] e"), test_run("[3 1 2]"));
+    assert_eq!(test_run("[3 1 3 2 1] c"), test_run("[0 1 0 2 1]"));
+    assert_eq!(test_run("[[1 2] [3 4] [1 2]] e"), test_run("[[1 2] [3 4]]"));
+    assert_eq!(test_run("[[1 2] [3 4] [1 2]] c"), test_run("[0 1 0]"));
+    assert_eq!(
+        test_run("{[3 4 5 3 4 5] [[2] [3]]} ~e"),
+        test_run("{[3 4 5] [[1] [3]]}")
+    );
+    assert_eq!(test_run("{[3 4 5 3 4 5] [[2] [3]]} ~c"), test_run("[0 0]"));
+}
+
+#[cfg(test)]
+#[test]
+fn where_() {
+    use crate::test_run;
+    assert_eq!(test_run("[0 1 0 2] x"), test_run("[1 3]"));
+    assert_eq!(test_run("[[0 1] [1 0]] x"), test_run("[[0 1] [1 0]]"));
+    assert_eq!(test_run("[[0 1] [1 1]] :x P"), test_run("[1 1 1]"));
+    assert_eq!(
+        test_run("{[0 1 0 0 0 1] [[2] [3]]} ~x"),
+        test_run("[[0 1] [1 2]]")
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn fix_deform_and_unchunk() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2 3] @fix m"), test_run("[[1 3]]"));
+    assert_eq!(test_run("[1 2 3] ~@fix m"), test_run("[[1] [3]]"));
+    assert_eq!(test_run("5 @fix"), test_run("[5]"));
+    assert_eq!(test_run("[[1 2] [3 4]] ~@fix m"), test_run("[[1 1] [2 2]]"));
+    // Horizontally, axes are collapsed column by column and the data follows them
+    assert_eq!(
+        test_run("{24i [[2 3] [4 1]]} @deform m"),
+        test_run("[[2 4 3 1]]")
+    );
+    assert_eq!(
+        test_run("{24i [[2 3] [4 1]]} @deform f 6T"),
+        test_run("[0 4 8 1 5 9]")
+    );
+    assert_eq!(
+        test_run("{24i [[2 3] [4 1]]} ~@deform"),
+        test_run("{24i [[2] [3] [4] [1]]}")
+    );
+    assert_eq!(test_run("{6i [[2] [3]]} @deform"), test_run("6i [2 3]H"));
+    assert_eq!(test_run("12i 3C m"), test_run("[[4] [3]]"));
+    assert_eq!(test_run("12i 3C @unchunk"), test_run("12i"));
+    assert!(
+        test_error("[1 2 3] @unchunk").contains("Cannot unchunk an array with horizontal form [3]")
+    );
+}
@@ -70,9 +70,43 @@ impl<'a> Parser<'a> {
         items
     }
     fn item(&mut self) -> Option<Item> {
+        if let Some(item) = self.binding() {
+            return Some(item);
+        }
         let words = self.words()?;
         Some(Item::Words(words))
     }
+    fn binding(&mut self) -> Option<Item> {
+        let start = self.curr;
+        let name = self.ident()?;
+        if self.next_token_exact(Equals).is_none() {
+            self.curr = start;
+            return None;
+        }
+        let import_start = self.curr;
+        if self.ident().is_some_and(|ident| ident.value == "import") {
+            if let Some(path) = self.next_token_map(|t, _| match t {
+                Token::Str(s) => Some(s.clone()),
+                _ => None,
+            }) {
+                let prefix = name;
+                return Some(Item::Import(Import { prefix, path }));
+            }
+        }
+        self.curr = import_start;
+        let sig = self.next_token_map(|t, _| match t {
+            Token::Sig(sig) => Some(*sig),
+            _ => None,
+        });
+        let words = self.words().unwrap_or_default();
+        Some(Item::Binding(Binding { name, sig, words }))
+    }
+    fn ident(&mut self) -> Option<Sp<EcoString>> {
+        self.next_token_map(|t, _| match t {
+            Token::Ident(name) => Some(name.clone()),
+            _ => None,
+        })
+    }
     fn words(&mut self) -> Option<Vec<Sp<Word>>> {
         let mut words = Vec::new();
         while let Some(word) = self.word() {
@@ -90,6 +124,13 @@ impl<'a> Parser<'a> {
     fn term(&mut self) -> Option<Sp<Word>> {
         Some(if let Some(num) = self.number() {
             num.map(Word::Number)
+        } else if let Some(name) = self.ident() {
+            name.map(Word::Ident)
+        } else if let Some(s) = self.next_token_map(|t, _| match t {
+            Token::Str(s) => Some(s.clone()),
+            _ => None,
+        }) {
+            s.map(Word::Str)
         } else if let Some(mon) = self.next_token_map(|t, _| match t {
             Token::Primitive(Prim::Mon(p)) => Some(*p),
             _ => None,
@@ -114,6 +155,16 @@ impl<'a> Parser<'a> {
             let close = self.expect(CloseBracket);
             let span = open.merge(close);
             span.sp(Word::Array(Array { open, lines, close }))
+        } else if let Some(open) = self.next_token_exact(OpenCurly) {
+            self.newline();
+            let mut lines = Vec::new();
+            while let Some(line) = self.words() {
+                lines.push(line);
+                self.newline();
+            }
+            let close = self.expect(CloseCurly);
+            let span = open.merge(close);
+            span.sp(Word::Formed(Array { open, lines, close }))
         } else {
             return None;
         })
@@ -1,28 +1,23 @@
 use ecow::eco_vec;
 
-use crate::{Array, Ufel, UfelResult};
+use crate::{Array, Dyadic, Element, Ufel, UfelResult};
 
 pub fn pervade<A, B, C>(
-    a: Array<A>,
-    b: Array<B>,
+    mut a: Array<A>,
+    mut b: Array<B>,
     _a_depth: usize,
     _b_depth: usize,
     f: impl Fn(A, B) -> C,
     rt: &Ufel,
 ) -> UfelResult<Array<C>>
 where
-    A: Clone,
-    B: Clone,
+    A: Element,
+    B: Element,
     C: Clone + Default,
 {
     if a.form == b.form {
         let mut c_data = eco_vec![C::default(); a.form.elems()];
-        for ((a, b), c) in a
-            .data
-            .into_iter()
-            .zip(b.data.into_iter())
-            .zip(c_data.make_mut())
-        {
+        for ((a, b), c) in a.data.into_iter().zip(b.data).zip(c_data.make_mut()) {
             *c = f(a, b);
         }
         Ok(Array::new(a.form, c_data.into()))
@@ -48,6 +43,18 @@ where
             }
         }
         Ok(Array::new(a.form, c_data.into()))
+    } else if let Some((a_fill, b_fill)) = A::fill(rt).zip(B::fill(rt)) {
+        let a_target = a.form.fill_target(&b.form);
+        let b_target = b.form.fill_target(&a.form);
+        if !a_target.prefixes_match(&b_target) {
+            return Err(rt.error(format!(
+                "Forms {:?} and {:?} are not compatible, even with fill",
+                a.form, b.form
+            )));
+        }
+        a.fill_to(&a_target, a_fill);
+        b.fill_to(&b_target, b_fill);
+        pervade(a, b, _a_depth, _b_depth, f, rt)
     } else {
         Err(rt.error(format!(
             "Forms {:?} and {:?} are not compatible",
@@ -56,6 +63,25 @@ where
     }
 }
 
+impl Dyadic {
+    /// Get the scalar function of a pervasive dyadic primitive
+    pub(crate) fn pervasive_fn(&self) -> Option<fn(f64, f64) -> f64> {
+        Some(match self {
+            Dyadic::Add => add::num_num,
+            Dyadic::Sub => sub::num_num,
+            Dyadic::Mul => mul::num_num,
+            Dyadic::Div => div::num_num,
+            Dyadic::Mod => mod_::num_num,
+            Dyadic::Eq => eq::num_num,
+            Dyadic::Lt => lt::num_num,
+            Dyadic::Gt => gt::num_num,
+            Dyadic::Min => min::num_num,
+            Dyadic::Max => max::num_num,
+            _ => return None,
+        })
+    }
+}
+
 // Monadic
 
 pub mod neg {
@@ -167,3 +193,27 @@ pub mod max {
         a.max(b)
     }
 }
+
+#[cfg(test)]
+#[test]
+fn fill() {
+    use crate::{test_error, test_run};
+    assert_eq!(test_run("[1 2] [3 4 5] _0+"), test_run("[4 6 5]"));
+    assert_eq!(test_run("[1 2] [3 4 5] _`1*"), test_run("[3 8 `5]"));
+    assert_eq!(test_run("_0[[1] [2 3]]"), test_run("[[1 0] [2 3]]"));
+    assert_eq!(
+        test_run("[1 2 3] _0y(i)"),
+        test_run("[[0 0 0] [0 1 0] [0 1 2]]")
+    );
+    // Column forms are extended along their columns
+    assert_eq!(
+        test_run("{[1 2] [[2] [1]]} {[1 2 3] [[3] [1]]} _0+"),
+        test_run("{[2 4 3] [[3] [1]]}")
+    );
+    // The fill value only applies inside the function
+    assert!(test_error("_0. [1 2] [3 4 5] +").contains("Forms [3] and [2] are not compatible"));
+    assert!(test_error("[[1] [2 3]]")
+        .contains("Cannot create array with different row forms [1] and [2]"));
+    assert!(test_error("[1 2] [3 4] [1 2] _+")
+        .contains("(_)fill value must be a scalar, but its form is [2]"));
+}
@@ -7,6 +7,10 @@ primitive!(Mon(Monadic), Dy(Dyadic), MonMod(Mod), DyMod(DyMod));
 prim!(Monadic,
     /// Do nothing with an array
     (Identity, "identity", '.'),
+    /// Duplicate the top value on the stack
+    (Dup, "duplicate", ':'),
+    /// Discard the top value on the stack
+    (Pop, "pop", ';'),
     /// Negate an array
     (Neg, "negate", '`'),
     /// Not an array
@@ -37,6 +41,26 @@ prim!(Monadic,
     (Transpose, "transpose", 't'),
     /// Swap the axes of an array's form
     (Swap, "swap", 'w'),
+    /// Flatten an array into a list
+    (Deshape, "deshape", 'f'),
+    /// Get the indices that would sort an array ascending
+    (Rise, "rise", 'u'),
+    /// Get the indices that would sort an array descending
+    (Fall, "fall", 'j'),
+    /// Sort an array
+    (Sort, "sort", 's'),
+    /// Remove duplicate rows from an array
+    (Deduplicate, "deduplicate", 'e'),
+    /// Assign a unique index to each unique row of an array
+    (Classify, "classify", 'c'),
+    /// Get the indices of an array's non-zero elements
+    (Where, "where", 'x'),
+    /// Add a unit axis to an array's form
+    (Fix, "fix"),
+    /// Collapse an array's form into a single row or column
+    (Deform, "deform"),
+    /// Merge the axes split by chunk
+    (Unchunk, "unchunk"),
 );
 prim!(Dyadic,
     /// Add two arrays
@@ -61,6 +85,38 @@ prim!(Dyadic,
     (Max, "max", 'X'),
     /// Chunk an array
     (Chunk, "chunk", 'C'),
+    /// Select rows from an array
+    (Select, "select", 'S'),
+    /// Pick an element from an array by an index vector
+    (Pick, "pick", 'P'),
+    /// Take the first rows of an array
+    (Take, "take", 'T'),
+    /// Drop the first rows of an array
+    (Drop, "drop", 'D'),
+    /// Rotate the rows of an array
+    (Rotate, "rotate", 'R'),
+    /// Join two arrays end-to-end
+    (Join, "join", 'J'),
+    /// Combine two arrays as rows of a new array
+    (Couple, "couple", 'K'),
+    /// Change the form of an array
+    (Reshape, "reshape", 'H'),
+    /// Check if an array is a row of another
+    (Member, "member of", 'B'),
+    /// Find the index of an array among the rows of another
+    (IndexOf, "index of", 'I'),
+    /// Filter or replicate the rows of an array by counts
+    (Keep, "keep", 'F'),
+    /// Get the sliding windows of an array
+    (Windows, "windows", 'W'),
+    /// Raise an error with a message if a condition is not all true
+    (Assert, "assert", 'A'),
+    /// Duplicate the second value on the stack to the top
+    (Over, "over"),
+    /// Label the axes of an array's shape with names
+    (Label, "label"),
+    /// Get the form position of the axis with a label
+    (AxisOf, "axis of"),
 );
 prim!(Mod,
     /// Call a function considering an array's form vertically rather than horizontally
@@ -68,6 +124,10 @@ prim!(Mod,
     /// This is the main thing that makes Ufel novel.
     /// Nested called flip back and forth between the two orientations.
     (Turn, "turn", '~'),
+    /// Call a function considering an array's form horizontally, regardless of any turns
+    (Horizontal, "horizontal"),
+    /// Call a function considering an array's form vertically, regardless of any turns
+    (Vertical, "vertical"),
     /// Call a function with two copies of the same value
     (Slf, "self", '\''),
     /// Call a function with its arguments reversed
@@ -86,16 +146,50 @@ prim!(Mod,
     (Scan, "scan", 'k'),
     /// Fold a fuction over an array and some accumulators
     (Fold, "fold", 'v'),
+    /// Call a function on each sliding window of an array
+    (Stencil, "stencil", '\\'),
+    /// Call a function on groups of rows of an array by key
+    (Group, "group"),
+    /// Call a function on runs of rows of an array with the same key
+    (Partition, "partition"),
+    /// Call a function on each row of some arrays
+    (Rows, "rows", 'y'),
+    /// Call a function on each element of some arrays
+    (Each, "each", '$'),
+    /// Call a function on every pair of rows of two arrays
+    (Table, "table", 'O'),
+    /// Call a function a number of times
+    (Repeat, "repeat", '<'),
+    /// Call one of several functions chosen by an index
+    (Switch, "switch", '?'),
+    /// Call a function's inverse
+    (Un, "un", 'V'),
+    /// Discard the top value on the stack, then call a function
+    (Gap, "gap"),
+    /// Call a function as if the axis at a form position were leading
+    (Axis, "axis"),
+    /// Call a function until the top of the stack stops changing
+    (FixedPoint, "fixed point", 'Y'),
 );
 prim!(DyMod,
     /// Call two functions on the same sets of values
     (Fork, "fork", '^'),
     /// Call two functions on different sets of values
     (Bracket, "bracket", '%'),
+    /// Contract two arrays along an axis with a generalized inner product
+    (Inner, "inner", 'Q'),
+    /// Call a function, padding mismatched forms with a fill value
+    (Fill, "fill", '_'),
+    /// Call a function while a condition function returns true
+    (Do, "do", '>'),
+    /// Call a function, then another, then the first's inverse
+    (Under, "under", 'U'),
+    /// Call a function, calling a handler with the error if it fails
+    (Try, "try", 'Z'),
 );
 
 pub trait PrimKind: Sized + Sequence {
-    fn glyph(&self) -> char;
+    fn glyph(&self) -> Option<char>;
     fn name(&self) -> &'static str;
     fn from_glyph(c: char) -> Option<Self>;
     fn from_name(name: &str) -> Option<Self>;
@@ -111,7 +205,7 @@ pub trait PrimKind: Sized + Sequence {
 fn glyph_collision() {
     for a in Primitive::all() {
         for b in Primitive::all() {
-            if a != b && a.glyph() == b.glyph() {
+            if a != b && a.glyph().is_some() && a.glyph() == b.glyph() {
                 panic!("{a:?} and {b:?} have the same glyph");
             }
         }
@@ -121,7 +215,7 @@ fn glyph_collision() {
 #[cfg(test)]
 #[test]
 fn gen_prim_tables() {
-    type Props<'a> = Vec<(&'a str, char, &'a str)>;
+    type Props<'a> = Vec<(&'a str, Option<char>, &'a str)>;
     let kinds: Vec<(&str, Props)> = vec![
         (
             "Monadic Functions",
@@ -160,10 +254,10 @@ fn gen_prim_tables() {
             md.push_str("| `");
             md.push_str(name);
             md.push_str("` | `");
-            md.push_str(&if glyph == '`' {
-                "` ` `".into()
-            } else {
-                glyph.to_string()
+            md.push_str(&match glyph {
+                Some('`') => "` ` `".into(),
+                Some(glyph) => glyph.to_string(),
+                None => format!("@{}", name.replace(' ', "")),
             });
             md.push_str("` | ");
             md.push_str(desc);
@@ -199,7 +293,7 @@ macro_rules! primitive {
         }
 
         impl PrimKind for Primitive {
-            fn glyph(&self) -> char {
+            fn glyph(&self) -> Option<char> {
                 match self {
                     $(Self::$name(p) => p.glyph(),)*
                 }
@@ -243,7 +337,7 @@ macro_rules! prim {
         $(
             #[doc = $doc:literal]
             $(#[doc = $doc2:literal])*
-            ($variant:ident, $name:literal, $glyph:literal)
+            ($variant:ident, $name:literal $(, $glyph:literal)?)
         ),* $(,)?
     ) => {
         #[derive(Clone, Copy, PartialEq, Eq, Hash, Sequence)]
@@ -257,24 +351,26 @@ macro_rules! prim {
 
         impl fmt::Debug for $prim {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                match self {
-                    $(Self::$variant => write!(f, "({}){}", $glyph, $name),)*
+                match self.glyph() {
+                    Some(glyph) => write!(f, "({}){}", glyph, self.name()),
+                    None => write!(f, "{}", self),
                 }
             }
         }
 
         impl fmt::Display for $prim {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                match self {
-                    $(Self::$variant => write!(f, "{}", $glyph),)*
+                match self.glyph() {
+                    Some(glyph) => write!(f, "{}", glyph),
+                    None => write!(f, "@{}", self.name().replace(' ', "")),
                 }
             }
         }
 
         impl PrimKind for $prim {
-            fn glyph(&self) -> char {
+            fn glyph(&self) -> Option<char> {
                 match self {
-                    $(Self::$variant => $glyph,)*
+                    $(Self::$variant => glyph!($($glyph)?),)*
                 }
             }
             fn name(&self) -> &'static str {
@@ -283,10 +379,7 @@ macro_rules! prim {
                 }
             }
             fn from_glyph(c: char) -> Option<Self> {
-                match c {
-                    $($glyph => Some(Self::$variant),)*
-                    _ => None,
-                }
+                Self::all().find(|p| p.glyph() == Some(c))
             }
             fn from_name(name: &str) -> Option<Self> {
                 match name {
@@ -308,3 +401,13 @@ macro_rules! prim {
     }
 }
 use prim;
+
+macro_rules! glyph {
+    () => {
+        None
+    };
+    ($glyph:literal) => {
+        Some($glyph)
+    };
+}
+use glyph;
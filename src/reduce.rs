@@ -1,6 +1,8 @@
-use ecow::eco_vec;
+use ecow::{eco_vec, EcoVec};
 
-use crate::{pervade::*, Array, Dyadic, Element, Mod, Ori, SigNode, Ufel, UfelResult};
+use crate::{
+    pervade::*, Array, DyMod, Dyadic, Element, Form, FormDims, Mod, Ori, SigNode, Ufel, UfelResult,
+};
 
 fn flip<T>(f: impl Fn(T, T) -> T) -> impl Fn(T, T) -> T {
     move |a, b| f(b, a)
@@ -106,3 +108,136 @@ pub fn fold(f: SigNode, rt: &mut Ufel) -> UfelResult {
     }
     Ok(())
 }
+
+pub fn inner(f: SigNode, g: SigNode, rt: &mut Ufel) -> UfelResult {
+    for h in [&f, &g] {
+        if h.sig != (2, 1) {
+            return Err(rt.error(format!(
+                "{:?} functions must have 2 arguments and 1 output, \
+                but one's signature is {:?}",
+                DyMod::Inner,
+                h.sig
+            )));
+        }
+    }
+    let ori = rt.ori();
+    let b = rt.pop(1)?;
+    let a = rt.pop(2)?;
+    // Arrays are contracted along the current orientation's shape,
+    // so vertically they must be single columns
+    let dims = |arr: &Array| -> UfelResult<FormDims> {
+        let normal = match ori {
+            Ori::Hori => arr.form.is_normal(),
+            Ori::Vert => arr.form.hori_rank() <= 1,
+        };
+        if !normal || arr.form.is_scalar() {
+            return Err(rt.error(format!(
+                "{:?} arrays must be non-scalar and have a single {} form {}, \
+                but one has form {:?}",
+                DyMod::Inner,
+                ori.str(),
+                if ori == Ori::Hori { "row" } else { "column" },
+                arr.form
+            )));
+        }
+        Ok(arr.form.dims().into())
+    };
+    let (a_dims, b_dims) = (dims(&a)?, dims(&b)?);
+    let k = *a_dims.last().unwrap();
+    if k != b_dims[0] {
+        return Err(rt.error(format!(
+            "Cannot {:?} arrays with contracted axes of length {k} and {}",
+            DyMod::Inner,
+            b_dims[0]
+        )));
+    }
+    let outer_a: usize = a_dims[..a_dims.len() - 1].iter().product();
+    let outer_b: usize = b_dims[1..].iter().product();
+    let outer_dims: FormDims = (a_dims[..a_dims.len() - 1].iter())
+        .chain(&b_dims[1..])
+        .copied()
+        .collect();
+    let outer_form = match ori {
+        _ if outer_dims.is_empty() => Form::scalar(),
+        Ori::Hori => Form::from(outer_dims),
+        Ori::Vert => Form::new(outer_dims.len(), 1, outer_dims),
+    };
+    let fast = (f.node.as_flipped_dy()).is_some_and(|(prim, _)| prim == Dyadic::Add)
+        && (g.node.as_flipped_dy()).is_some_and(|(prim, _)| prim == Dyadic::Mul);
+    if fast {
+        let mut data = eco_vec![0.0; outer_a * outer_b];
+        let slice = data.make_mut();
+        for i in 0..outer_a {
+            let row = &mut slice[i * outer_b..(i + 1) * outer_b];
+            for t in 0..k {
+                let x = a.data[i * k + t];
+                for (r, &y) in row.iter_mut().zip(&b.data[t * outer_b..(t + 1) * outer_b]) {
+                    *r += x * y;
+                }
+            }
+        }
+        rt.push(Array::new(outer_form, data.into()));
+        return Ok(());
+    }
+    let mut results = Vec::with_capacity(outer_a * outer_b);
+    for i in 0..outer_a {
+        let x: Array = a.data.slice(i * k..(i + 1) * k).into();
+        for j in 0..outer_b {
+            let y: Array = (0..k).map(|t| b.data[t * outer_b + j]).collect();
+            rt.push(x.clone());
+            rt.push(y);
+            rt.exec(g.node.clone())?;
+            reduce(f.clone(), rt)?;
+            results.push(rt.pop(1)?);
+        }
+    }
+    let mut data = EcoVec::with_capacity(results.len());
+    for res in results {
+        if !res.form.is_scalar() {
+            return Err(rt.error(format!(
+                "{:?} functions must produce scalars, but one result has form {:?}",
+                DyMod::Inner,
+                res.form
+            )));
+        }
+        data.push(res.data[0]);
+    }
+    rt.push(Array::new(outer_form, data.into()));
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn inner_() {
+    use crate::{test_error, test_run};
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [[5 6] [7 8]] Q+*"),
+        test_run("[[19 22] [43 50]]")
+    );
+    // Contracting two lists gives a scalar
+    assert_eq!(test_run("[1 2 3] [4 5 6] Q+*"), test_run("32"));
+    assert_eq!(test_run("[1 2 3] [4 5 6] Q(+)(*.)"), test_run("32"));
+    assert_eq!(test_run("[[1 2] [3 4]] [5 6] Q+*"), test_run("[17 39]"));
+    // Functions other than add and multiply take the general path
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [[5 6] [7 8]] Q(+)(*.)"),
+        test_run("[[19 22] [43 50]]")
+    );
+    assert_eq!(
+        test_run("[[1 2] [3 4]] [[5 6] [7 8]] Q(X)(N)"),
+        test_run("[[2 2] [4 4]]")
+    );
+    // Vertically, single columns are contracted
+    assert_eq!(
+        test_run("{[1 2 3 4] [[2] [2]]} {[5 6 7 8] [[2] [2]]} ~Q+*"),
+        test_run("{[19 22 43 50] [[2] [2]]}")
+    );
+    assert!(test_error("[1 2] [3 4 5] Q+*")
+        .contains("Cannot (Q)inner arrays with contracted axes of length 2 and 3"));
+    assert!(test_error("[[1 2] [3 4]] 3 Q+*")
+        .contains("(Q)inner arrays must be non-scalar and have a single horizontal form row"));
+    assert!(test_error("[[1 2] [3 4]] [[5 6] [7 8]] Q(+)(K)")
+        .contains("(Q)inner functions must produce scalars"));
+    assert!(test_error("[1 2] [3 4] Q(+)(.)")
+        .contains("(Q)inner functions must have 2 arguments and 1 output"));
+}
@@ -1,31 +1,73 @@
-use std::mem::take;
+use std::mem::{replace, take};
 
-use ecow::EcoString;
+use ecow::{EcoString, EcoVec};
 
 use crate::{
-    reduce::{fold, reduce},
-    Array, Assembly, Compiler, DyMod, Dyadic, InputSrc, Mod, Monadic, Node, Ori, SigNode,
+    loops::{axis, do_, each, fixed_point, group, partition, repeat, rows, stencil, table},
+    reduce::{fold, inner, reduce},
+    Array, Assembly, Compiler, DyMod, Dyadic, ImplPrim, InputSrc, Mod, Monadic, Node, Ori, SigNode,
     UfelError, UfelErrorKind, UfelResult,
 };
 
-#[derive(Clone, Default)]
+/// The default maximum number of iterations of a looping modifier
+pub const DEFAULT_ITERATION_LIMIT: usize = 1_000_000;
+/// The default maximum depth of nested function calls
+pub const DEFAULT_RECURSION_LIMIT: usize = 1000;
+/// The native stack left when execution moves to a new stack segment
+const STACK_RED_ZONE: usize = 256 * 1024;
+/// The size of each new native stack segment
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+#[derive(Clone)]
 pub struct Ufel {
     pub asm: Assembly,
     stack: Vec<Array>,
     trace: Vec<usize>,
     ori: Ori,
+    fill: Option<f64>,
+    iteration_limit: usize,
+    /// The number of function calls currently being executed inside one another
+    depth: usize,
+    recursion_limit: usize,
+}
+
+impl Default for Ufel {
+    fn default() -> Self {
+        Self {
+            asm: Assembly::default(),
+            stack: Vec::new(),
+            trace: Vec::new(),
+            ori: Ori::default(),
+            fill: None,
+            iteration_limit: DEFAULT_ITERATION_LIMIT,
+            depth: 0,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+        }
+    }
 }
 
 impl Ufel {
     pub fn new() -> Self {
         Self::default()
     }
+    /// Set the maximum number of iterations of a looping modifier
+    pub fn with_iteration_limit(mut self, limit: usize) -> Self {
+        self.iteration_limit = limit;
+        self
+    }
+    pub fn iteration_limit(&self) -> usize {
+        self.iteration_limit
+    }
+    /// Set the maximum depth of nested function calls
+    pub fn with_recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
     pub fn run(&mut self, src: InputSrc, text: impl Into<EcoString>) -> UfelResult {
         let mut compiler = Compiler::default();
         compiler.load(src, text.into())?;
         self.asm = compiler.asm;
-        self.exec(self.asm.root.clone())?;
-        Ok(())
+        let root = self.asm.root.clone();
+        self.exec(root)
     }
     pub fn run_str(&mut self, text: impl Into<EcoString>) -> UfelResult {
         self.run(InputSrc::Str, text.into())
@@ -33,7 +75,16 @@ impl Ufel {
     pub fn ori(&self) -> Ori {
         self.ori
     }
+    /// Get the current fill value
+    pub fn fill(&self) -> Option<f64> {
+        self.fill
+    }
     pub fn exec(&mut self, node: Node) -> UfelResult {
+        // Nested nodes recurse natively, so the stack is grown
+        // as needed regardless of the calling thread's stack size
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || self.exec_node(node))
+    }
+    fn exec_node(&mut self, node: Node) -> UfelResult {
         // Uncomment to debug
         // for val in &self.stack {
         //     print!("{val} ");
@@ -57,10 +108,21 @@ impl Ufel {
                 rt.push(arr);
                 Ok(())
             })?,
+            Node::Formed(inner, span) => self.with_span(span, |rt| {
+                rt.exec(*inner)?;
+                let form = rt.pop(1)?;
+                let data = rt.pop(2)?;
+                let arr = data.formed(form, rt)?;
+                rt.push(arr);
+                Ok(())
+            })?,
             Node::Mon(prim, span) => self.with_span(span, |rt| rt.monadic(prim))?,
             Node::Dy(prim, span) => self.with_span(span, |rt| rt.dyadic(prim))?,
             Node::Mod(prim, f, span) => self.with_span(span, |rt| rt.mon_mod(prim, *f))?,
             Node::DyMod(prim, f, g, span) => self.with_span(span, |rt| rt.dy_mod(prim, *f, *g))?,
+            Node::Call(index, _, span) => self.with_span(span, |rt| rt.call(index))?,
+            Node::Impl(prim, span) => self.with_span(span, |rt| rt.impl_prim(prim))?,
+            Node::Switch(branches, span) => self.with_span(span, |rt| rt.switch(branches))?,
         }
         Ok(())
     }
@@ -80,6 +142,11 @@ impl Ufel {
         let a = self.pop(1)?;
         let res = match prim {
             Monadic::Identity => a,
+            Monadic::Dup => {
+                self.push(a.clone());
+                a
+            }
+            Monadic::Pop => return Ok(()),
             Monadic::Neg => a.neg(),
             Monadic::Not => a.not(),
             Monadic::Abs => a.abs(),
@@ -95,6 +162,20 @@ impl Ufel {
             Monadic::Reverse => a.reverse(self.ori()),
             Monadic::Transpose => a.transpose(self)?,
             Monadic::Swap => a.swap(self)?,
+            Monadic::Deshape => a.deshape(),
+            Monadic::Rise => a.rise(self)?,
+            Monadic::Fall => a.fall(self)?,
+            Monadic::Sort => a.sort(self)?,
+            Monadic::Deduplicate => a.deduplicate(self)?,
+            Monadic::Classify => a.classify(self)?,
+            Monadic::Where => a.where_(self)?,
+            Monadic::Fix => {
+                let mut a = a;
+                a.form.fix(self.ori);
+                a
+            }
+            Monadic::Deform => a.deform(self)?,
+            Monadic::Unchunk => a.unchunk(self)?,
         };
         self.push(res);
         Ok(())
@@ -114,6 +195,26 @@ impl Ufel {
             Dyadic::Min => a.min(b, 0, 0, self)?,
             Dyadic::Max => a.max(b, 0, 0, self)?,
             Dyadic::Chunk => b.chunk(a, self)?,
+            Dyadic::Select => b.select(a, self)?,
+            Dyadic::Pick => b.pick(a, self)?,
+            Dyadic::Take => b.take(a, self)?,
+            Dyadic::Drop => b.drop(a, self)?,
+            Dyadic::Rotate => b.rotate(a, self)?,
+            Dyadic::Join => b.join(a, self)?,
+            Dyadic::Couple => b.couple(a, self)?,
+            Dyadic::Reshape => b.reshape(a, self)?,
+            Dyadic::Member => b.member(a, self)?,
+            Dyadic::IndexOf => b.index_of(a, self)?,
+            Dyadic::Keep => b.keep(a, self)?,
+            Dyadic::Windows => b.windows(a, self)?,
+            Dyadic::Label => b.label(a, self)?,
+            Dyadic::AxisOf => b.axis_of(a, self)?,
+            Dyadic::Assert => return self.assert(b, a),
+            Dyadic::Over => {
+                self.push(b.clone());
+                self.push(a);
+                b
+            }
         };
         self.push(res);
         Ok(())
@@ -126,6 +227,17 @@ impl Ufel {
                 self.ori = !self.ori;
                 res?
             }
+            Mod::Horizontal | Mod::Vertical => {
+                let ori = if prim == Mod::Horizontal {
+                    Ori::Hori
+                } else {
+                    Ori::Vert
+                };
+                let prev = replace(&mut self.ori, ori);
+                let res = self.exec(f.node);
+                self.ori = prev;
+                res?
+            }
             Mod::Slf => {
                 let a = self.pop(1)?;
                 self.push(a.clone());
@@ -139,6 +251,11 @@ impl Ufel {
                 self.push(b);
                 self.exec(f.node)?;
             }
+            Mod::Axis => axis(f, self)?,
+            Mod::Gap => {
+                self.pop(1)?;
+                self.exec(f.node)?;
+            }
             Mod::Dip => {
                 let a = self.pop(1);
                 self.exec(f.node)?;
@@ -175,9 +292,99 @@ impl Ufel {
             Mod::Reduce => reduce(f, self)?,
             Mod::Scan => return Err(self.error("Scan is not yet implemented")),
             Mod::Fold => fold(f, self)?,
+            Mod::Stencil => stencil(f, self)?,
+            Mod::Group => group(f, self)?,
+            Mod::Partition => partition(f, self)?,
+            Mod::Rows => rows(f, self)?,
+            Mod::Each => each(f, self)?,
+            // The compiler turns every switch into a switch node,
+            // so only a hand-built node can get here
+            Mod::Switch => {
+                return Err(self.error(format!(
+                    "{:?} must be compiled to a switch node",
+                    Mod::Switch
+                )))
+            }
+            // The compiler has already inverted the function
+            Mod::Un => self.exec(f.node)?,
+            Mod::Repeat => repeat(f, self)?,
+            Mod::FixedPoint => fixed_point(f, self)?,
+            Mod::Table => table(f, self)?,
         }
         Ok(())
     }
+    fn call(&mut self, index: usize) -> UfelResult {
+        if self.depth >= self.recursion_limit {
+            return Err(self.error(format!(
+                "Recursion limit of {} exceeded",
+                self.recursion_limit
+            )));
+        }
+        let f = self.asm.functions[index].node.clone();
+        self.depth += 1;
+        let res = self.exec(f);
+        self.depth -= 1;
+        res
+    }
+    fn assert(&self, cond: Array, message: Array) -> UfelResult {
+        if cond.data.iter().all(|&x| x == 1.0) {
+            return Ok(());
+        }
+        let span = self.trace.last().copied().unwrap_or(0);
+        let span = self.asm.spans[span];
+        let error = self.asm.inputs.error(span, message.to_string());
+        Err(UfelErrorKind::Assert(error, message.into()).into())
+    }
+    fn try_(&mut self, f: SigNode, handler: SigNode) -> UfelResult {
+        let args = self.copy_n(f.sig.args)?;
+        let height = self.stack.len();
+        let (ori, fill) = (self.ori, self.fill);
+        let Err(e) = self.exec(f.node) else {
+            return Ok(());
+        };
+        let value = match e.kind {
+            UfelErrorKind::Assert(_, value) => *value,
+            UfelErrorKind::Run(e) => e.message.as_str().into(),
+            _ => return Err(e),
+        };
+        self.ori = ori;
+        self.fill = fill;
+        self.stack.truncate(height - args.len());
+        let arg_count = handler.sig.args.saturating_sub(1).min(args.len());
+        self.stack
+            .extend(args.into_iter().skip(f.sig.args - arg_count));
+        // A handler that takes no arguments does not get the error value
+        if handler.sig.args > 0 {
+            self.push(value);
+        }
+        self.exec(handler.node)
+    }
+    fn impl_prim(&mut self, prim: ImplPrim) -> UfelResult {
+        let a = self.pop(1)?;
+        let res = match prim {
+            ImplPrim::Untranspose => a.untranspose(self)?,
+        };
+        self.push(res);
+        Ok(())
+    }
+    fn switch(&mut self, branches: EcoVec<SigNode>) -> UfelResult {
+        let index = self.pop(1)?;
+        let index = match index.form.is_scalar().then(|| index.data[0]) {
+            Some(i) if i >= 0.0 && i.fract() == 0.0 && (i as usize) < branches.len() => i as usize,
+            _ => {
+                return Err(self.error(format!(
+                    "{:?} index must be a natural number less than \
+                    the number of branches {}, but it is {index}",
+                    Mod::Switch,
+                    branches.len()
+                )))
+            }
+        };
+        // Branches with fewer arguments leave the extra values below their outputs
+        let args = branches.iter().map(|br| br.sig.args).max().unwrap_or(0);
+        self.require_height(args)?;
+        self.exec(branches[index].node.clone())
+    }
     fn dy_mod(&mut self, prim: DyMod, f: SigNode, g: SigNode) -> UfelResult {
         match prim {
             DyMod::Fork => {
@@ -192,6 +399,29 @@ impl Ufel {
                 self.stack.extend(g_args);
                 self.exec(g.node)?;
             }
+            DyMod::Inner => inner(f, g, self)?,
+            DyMod::Try => self.try_(f, g)?,
+            // The compiler has already appended the inverse to the second function
+            DyMod::Under => {
+                self.exec(f.node)?;
+                self.exec(g.node)?;
+            }
+            DyMod::Do => do_(f, g, self)?,
+            DyMod::Fill => {
+                self.exec(f.node)?;
+                let fill = self.pop(1)?;
+                if !fill.form.is_scalar() {
+                    return Err(self.error(format!(
+                        "{:?} value must be a scalar, but its form is {:?}",
+                        DyMod::Fill,
+                        fill.form
+                    )));
+                }
+                let prev = self.fill.replace(fill.data[0]);
+                let res = self.exec(g.node);
+                self.fill = prev;
+                res?;
+            }
         }
         Ok(())
     }
@@ -203,11 +433,11 @@ impl Ufel {
             .pop()
             .ok_or_else(|| self.error(format!("Stack was empty when getting argument {n}")))
     }
-    fn copy_n(&self, n: usize) -> UfelResult<Vec<Array>> {
+    pub(crate) fn copy_n(&self, n: usize) -> UfelResult<Vec<Array>> {
         self.require_height(n)?;
         Ok(self.stack[self.stack.len() - n..].to_vec())
     }
-    fn take_n(&mut self, n: usize) -> UfelResult<Vec<Array>> {
+    pub(crate) fn take_n(&mut self, n: usize) -> UfelResult<Vec<Array>> {
         self.require_height(n)?;
         Ok(self.stack.split_off(self.stack.len() - n))
     }
@@ -224,3 +454,156 @@ impl Ufel {
         take(&mut self.stack)
     }
 }
+
+/// Run some code and get the resulting stack, panicking if it fails
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn test_run(code: &str) -> Vec<Array> {
+    let mut rt = Ufel::new();
+    if let Err(e) = rt.run_str(code) {
+        panic!("{code:?} failed: {e}");
+    }
+    rt.take_stack()
+}
+
+/// Run some code and get its error messages, panicking if it succeeds
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn test_error(code: &str) -> String {
+    let mut rt = Ufel::new();
+    let Err(e) = rt.run_str(code) else {
+        panic!("{code:?} did not fail");
+    };
+    let mut message = e.to_string();
+    for e in e.multi {
+        message.push('\n');
+        message.push_str(&e.to_string());
+    }
+    message
+}
+
+#[cfg(test)]
+#[test]
+fn switch() {
+    assert_eq!(test_run("3 0?(1+|2*)"), test_run("4"));
+    assert_eq!(test_run("3 1?(1+|2*)"), test_run("6"));
+    assert_eq!(test_run("1 2 0?(+|;)"), test_run("3"));
+    assert_eq!(test_run("1 2 1?(+|;)"), test_run("1"));
+    assert!(test_error("3 2?(1+|2*)").contains(
+        "(?)switch index must be a natural number less than the number of branches 2, but it is 2"
+    ));
+    assert!(test_error("3 [0 1]?(1+|2*)").contains("but it is [0 1]"));
+    assert!(test_error("1 2 0?(+|.)").contains(
+        "(?)switch branch signature |1.1 is not compatible with the first branch's signature |2.1"
+    ));
+    // Every compile error is reported
+    let errors = test_error("1 2 0?(+|.)\n3 4 0?(+|.)");
+    assert_eq!(errors.matches("is not compatible").count(), 2, "{errors}");
+    // A switch outside of a switch node is an error rather than a panic
+    let mut rt = Ufel::new();
+    rt.run_str("1 2 +").unwrap();
+    let f = Node::Push(2.0.into()).sig_node();
+    let e = rt.exec(Node::Mod(Mod::Switch, f.into(), 0)).unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("(?)switch must be compiled to a switch node"));
+}
+
+#[cfg(test)]
+#[test]
+fn assert_and_try() {
+    assert_eq!(test_run("1 2 A 5"), test_run("5"));
+    assert_eq!(test_run("[1 1] 2 A"), test_run(""));
+    let error = test_error("[1 0] 2 A");
+    assert!(error.starts_with("Assertion failed at") && error.ends_with(": 2"));
+    assert_eq!(test_run("1 2 Z(+)(;0)"), test_run("3"));
+    // The handler gets the assertion value or the error message
+    assert_eq!(test_run("5 Z(0 7 A 1+)(10+)"), test_run("17"));
+    // "Forms [3] and [2] are not compatible"
+    assert_eq!(test_run("[1 2] [3 4 5] Z(+)(n)"), test_run("36"));
+    // The handler's other arguments are the function's original arguments
+    assert_eq!(test_run("5 Z(0 7 A 1+)(+)"), test_run("12"));
+    assert_eq!(test_run("[1 2] [3 4 5] Z(+)(;;;0)"), test_run("0"));
+    // A handler with no arguments replaces the function's arguments entirely
+    assert_eq!(test_run("[1 2] [3 4 5] Z(+)(0)"), test_run("0"));
+    assert_eq!(test_run("9 [1 2] [3 4 5] Z(+)(0)"), test_run("9 0"));
+    assert!(test_error("1 Z(0 3 A)(;2)").contains(
+        "(Z)try's handler must have 0 outputs and at most 1 arguments, but its signature is |1.1"
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn recursion() {
+    let fact = "@fact = |1.1 '(2L ?('(1- @fact *)|0*1+))\n";
+    assert_eq!(test_run(&format!("{fact}5 @fact")), test_run("120"));
+    assert_eq!(test_run("@f = |2 +\n1 2 @f"), test_run("3"));
+    assert!(test_error("@f = |1.10 (1+)\n2 @f")
+        .contains("@f's signature |1.1 does not match its annotation |1.10"));
+    assert!(test_error("@f = |1. (1+)\n2 @f").contains("Invalid signature"));
+    assert!(test_error("@f = | (1+)\n2 @f").contains("Invalid signature"));
+    // The limit counts calls, and deep recursion fails with an error
+    // rather than overflowing the native stack
+    let deep = |n, limit| {
+        let code = format!("{fact}{n} @fact");
+        Ufel::new().with_recursion_limit(limit).run_str(code)
+    };
+    assert!(deep(DEFAULT_RECURSION_LIMIT, DEFAULT_RECURSION_LIMIT).is_ok());
+    assert!(deep(10, 10).is_ok());
+    let e = deep(10, 9).unwrap_err();
+    assert!(e.to_string().contains("Recursion limit of 9 exceeded"));
+    let e = test_error("@f = |1.1 (1+ @f)\n0 @f");
+    assert!(e.contains(&format!(
+        "Recursion limit of {DEFAULT_RECURSION_LIMIT} exceeded"
+    )));
+    // Spawned threads with small stacks are just as safe
+    let e = std::thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(|| test_error("@f = |1.1 (1+ @f)\n0 @f"))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(e.contains("Recursion limit"));
+}
+
+#[cfg(test)]
+#[test]
+fn stack_primitives() {
+    assert_eq!(test_run("1 2 :"), test_run("1 2 2"));
+    assert_eq!(test_run("1 2 ;"), test_run("1"));
+    assert_eq!(test_run("1 2 @over"), test_run("1 2 1"));
+    assert_eq!(test_run("1 2 3 @gap+"), test_run("3"));
+    assert_eq!(test_run("1 2 @gap."), test_run("1"));
+    // Signatures account for the values each one adds or removes
+    assert_eq!(test_run("@f = |1.2 :\n1 @f"), test_run("1 1"));
+    assert_eq!(test_run("@f = |1.0 ;\n1 @f"), test_run(""));
+    assert_eq!(test_run("@f = |2.3 @over\n1 2 @f"), test_run("1 2 1"));
+    assert_eq!(test_run("@f = |3.1 @gap+\n1 2 3 @f"), test_run("3"));
+    assert!(test_error("@f = |1.1 :\n1 @f")
+        .contains("@f's signature |1.2 does not match its annotation |1.1"));
+    assert!(test_error(";").contains("Stack was empty when getting argument 1"));
+    assert!(test_error("1 @over").contains("Stack was empty when getting argument 2"));
+}
+
+#[cfg(test)]
+#[test]
+fn orientation() {
+    let arr = "~[[1 2] [3 4]] ";
+    assert_eq!(test_run(&format!("{arr}n")), test_run("4"));
+    assert_eq!(test_run(&format!("{arr}~n")), test_run("2"));
+    assert_eq!(test_run(&format!("{arr}~~n")), test_run("4"));
+    // Absolute orientations ignore any surrounding turns
+    assert_eq!(test_run(&format!("{arr}@vertical(n)")), test_run("2"));
+    assert_eq!(test_run(&format!("{arr}~~@vertical(n)")), test_run("2"));
+    assert_eq!(test_run(&format!("{arr}~@horizontal(n)")), test_run("4"));
+    assert_eq!(test_run(&format!("{arr}@vertical(~n)")), test_run("4"));
+    // The orientation is restored afterward
+    assert_eq!(
+        test_run(&format!("{arr}~(@horizontal(.) n)")),
+        test_run("2")
+    );
+    assert_eq!(
+        test_run(&format!("@f = (@vertical n)\n{arr}: @f ,~@f")),
+        test_run("2 2")
+    );
+}
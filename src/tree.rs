@@ -15,12 +15,23 @@ node!(
     Run(nodes(EcoVec<Node>)),
     Push(val(Array)),
     Array(len(usize), inner(Box<Node>), span(usize)),
+    Formed(inner(Box<Node>), span(usize)),
     Mon(prim(Monadic), span(usize)),
     Dy(prim(Dyadic), span(usize)),
     Mod(prim(Mod), f(Box<SigNode>), span(usize)),
     DyMod(prim(DyMod), f(Box<SigNode>), g(Box<SigNode>), span(usize)),
+    Switch(branches(EcoVec<SigNode>), span(usize)),
+    Impl(prim(ImplPrim), span(usize)),
+    Call(index(usize), sig(Signature), span(usize)),
 );
 
+/// Primitives that can only be created by the compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImplPrim {
+    /// The inverse of [`Monadic::Transpose`]
+    Untranspose,
+}
+
 /// A node with a signature
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct SigNode {
@@ -148,7 +159,7 @@ impl Node {
     /// Mutably iterate over the nodes of this node
     ///
     /// Transforms the node into a [`Node::Run`] if it is not already a [`Node::Run`]
-    pub fn iter_mut(&mut self) -> slice::IterMut<Self> {
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Self> {
         self.as_mut_slice().iter_mut()
     }
     /// Push a node onto the end of the node
@@ -266,6 +277,11 @@ impl fmt::Debug for Node {
                 inner.fmt(f)?;
                 write!(f, "]")
             }
+            Node::Formed(inner, _) => {
+                write!(f, "{{")?;
+                inner.fmt(f)?;
+                write!(f, "}}")
+            }
             Node::Mon(prim, _) => write!(f, "{prim}"),
             Node::Dy(prim, _) => write!(f, "{prim}"),
             Node::Mod(prim, ff, _) => f.debug_tuple(&prim.to_string()).field(&ff.node).finish(),
@@ -274,6 +290,15 @@ impl fmt::Debug for Node {
                 .field(&ff.node)
                 .field(&g.node)
                 .finish(),
+            Node::Impl(prim, _) => write!(f, "{prim:?}"),
+            Node::Call(index, ..) => write!(f, "call {index}"),
+            Node::Switch(branches, _) => {
+                let mut tuple = f.debug_tuple(&Mod::Switch.to_string());
+                for branch in branches {
+                    tuple.field(&branch.node);
+                }
+                tuple.finish()
+            }
         }
     }
 }
@@ -308,13 +333,38 @@ impl Node {
                         self.node(inner);
                         self.handle(*len, 1);
                     }
+                    Node::Formed(inner, _) => {
+                        self.node(inner);
+                        self.handle(2, 1);
+                    }
                     Node::Push(_) => self.handle(0, 1),
-                    Node::Mon(_, _) => self.handle(1, 1),
+                    Node::Mon(Monadic::Dup, _) => self.handle(1, 2),
+                    Node::Mon(Monadic::Pop, _) => self.handle(1, 0),
+                    Node::Mon(_, _) | Node::Impl(_, _) => self.handle(1, 1),
+                    Node::Dy(Dyadic::Assert, _) => self.handle(2, 0),
+                    Node::Dy(Dyadic::Over, _) => self.handle(2, 3),
+                    Node::Call(_, sig, _) => self.handle(sig.args, sig.outputs),
                     Node::Dy(_, _) => self.handle(2, 1),
                     Node::Mod(m, f, _) => match m {
-                        Mod::Turn | Mod::Fold => self.handle(f.sig.args, f.sig.outputs),
+                        Mod::Turn
+                        | Mod::Horizontal
+                        | Mod::Vertical
+                        | Mod::Fold
+                        | Mod::Rows
+                        | Mod::Each
+                        | Mod::FixedPoint => self.handle(f.sig.args, f.sig.outputs),
+                        // Switches are compiled to switch nodes, so they never appear here
+                        Mod::Un | Mod::Switch => self.handle(f.sig.args, f.sig.outputs),
+                        Mod::Repeat => {
+                            self.handle(1, 0);
+                            self.handle(f.sig.args, f.sig.outputs)
+                        }
                         Mod::Dip => self.handle(f.sig.args + 1, f.sig.outputs + 1),
+                        Mod::Gap | Mod::Axis => self.handle(f.sig.args + 1, f.sig.outputs),
                         Mod::Reduce | Mod::Scan => self.handle(1, 1),
+                        Mod::Stencil | Mod::Group | Mod::Partition | Mod::Table => {
+                            self.handle(2, 1)
+                        }
                         Mod::Slf => {
                             self.handle(1, 2);
                             self.handle(f.sig.args, f.sig.outputs)
@@ -333,7 +383,31 @@ impl Node {
                         DyMod::Bracket => {
                             self.handle(f.sig.args + g.sig.args, f.sig.outputs + g.sig.outputs)
                         }
+                        DyMod::Inner => self.handle(2, 1),
+                        DyMod::Try => {
+                            self.handle(f.sig.args.max(g.sig.args.saturating_sub(1)), f.sig.outputs)
+                        }
+                        DyMod::Under => {
+                            self.handle(f.sig.args, f.sig.outputs);
+                            self.handle(g.sig.args, g.sig.outputs)
+                        }
+                        DyMod::Do => {
+                            let args = f.sig.args.max(g.sig.args);
+                            self.handle(args, args - f.sig.args + f.sig.outputs)
+                        }
+                        DyMod::Fill => {
+                            self.handle(f.sig.args, f.sig.outputs);
+                            self.handle(1, 0);
+                            self.handle(g.sig.args, g.sig.outputs)
+                        }
                     },
+                    Node::Switch(branches, _) => {
+                        let args = branches.iter().map(|br| br.sig.args).max().unwrap_or(0);
+                        let net = branches
+                            .first()
+                            .map_or(0, |br| br.sig.outputs as i32 - br.sig.args as i32);
+                        self.handle(args + 1, (args as i32 + net).max(0) as usize)
+                    }
                 }
             }
         }